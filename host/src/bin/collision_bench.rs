@@ -0,0 +1,184 @@
+//! Times the collision checks of the old bit-by-bit engine against the
+//! bitmask engine on the same positions: the piece in the air over the
+//! stack as seeded games of the bitmask engine left them.
+//!
+//! Both engines are loaded with each position and asked the same two
+//! questions, whether the piece fits moved or turned (`bounds_check`
+//! against `fits`) and whether it has landed (`reached_bottom` against
+//! `fits` one row down). The answers are checked to agree, so only the
+//! cost of asking differs. Moves past the floor are left out, the old
+//! check indexes past its grid there.
+
+use std::time::{Duration, Instant};
+
+use bitvec::bitarr;
+use oorandom::Rand32;
+use pinecil_tetris::game::{Block, Tetris};
+use pinecil_tetris_host::legacy;
+
+const GAMES: u64 = 100;
+const TICKS: u32 = 2_000;
+
+// Passes over all positions, the time is summed over them.
+const ROUNDS: u32 = 20;
+
+struct Position {
+    game: Tetris,
+    legacy: legacy::Tetris,
+    // the piece moved and turned every way, in both engines' terms
+    candidates: Vec<(Block, legacy::Block)>,
+}
+
+// The same cells as `block` in the old engine's terms, with the box
+// pulled up against them, the old landing check can't take a box that
+// hangs past the wall.
+fn to_legacy(block: &Block) -> legacy::Block {
+    let left = block.cells().map(|(x, _)| x).min().unwrap();
+    let top = block.cells().map(|(_, y)| y).min().unwrap();
+    let mut shape = [bitarr![0; 4]; 4];
+    for (x, y) in block.cells() {
+        shape[(y - top) as usize].set((x - left) as usize, true);
+    }
+    legacy::Block {
+        shape,
+        size: 4,
+        pos: (left, top),
+    }
+}
+
+fn position(game: &Tetris, block: Block) -> Position {
+    let height = game.height() as i32;
+    let mut candidates = Vec::new();
+    for (dx, dy, turns) in [
+        (0, 0, 0),
+        (-1, 0, 0),
+        (1, 0, 0),
+        (0, 1, 0),
+        (0, 0, 1),
+        (0, 0, 3),
+    ] {
+        let mut moved = block;
+        moved.pos.0 += dx;
+        moved.pos.1 += dy;
+        moved.rotation = (moved.rotation + turns) % 4;
+        if moved.cells().all(|(_, y)| y < height) {
+            candidates.push((moved, to_legacy(&moved)));
+        }
+    }
+
+    let mut legacy = legacy::Tetris::init();
+    legacy.set_position(game.get_grid(), to_legacy(&block));
+    Position {
+        game: Tetris::from_bytes(&game.to_bytes()).unwrap(),
+        legacy,
+        candidates,
+    }
+}
+
+// Every position a piece was in the air in over seeded games of random
+// input.
+fn positions() -> Vec<Position> {
+    let mut positions = Vec::new();
+    for seed in 0..GAMES {
+        let mut game = Tetris::init();
+        game.reset_seeded(seed);
+        let mut input = Rand32::new(seed);
+        for _ in 0..TICKS {
+            if game.has_ended() {
+                break;
+            }
+            match input.rand_range(0..4) {
+                0 => game.rotate_block(),
+                1 => game.move_block(),
+                _ => {}
+            }
+            game.run();
+            if let Some(block) = game.get_block() {
+                positions.push(position(&game, block));
+            }
+        }
+    }
+    positions
+}
+
+// Runs `check` over all positions `ROUNDS` times, returns the time it
+// took and how often it said yes.
+fn time<F: FnMut(&mut Position) -> u64>(
+    positions: &mut [Position],
+    mut check: F,
+) -> (Duration, u64) {
+    let start = Instant::now();
+    let mut yes = 0;
+    for _ in 0..ROUNDS {
+        for position in positions.iter_mut() {
+            yes += check(position);
+        }
+    }
+    (start.elapsed(), yes)
+}
+
+fn print(
+    name: &str,
+    checks: usize,
+    (old, old_yes): (Duration, u64),
+    (new, new_yes): (Duration, u64),
+) {
+    assert_eq!(old_yes, new_yes, "{}: the engines disagree", name);
+    let checks = (checks as u64 * ROUNDS as u64) as f64;
+    let old = old.as_nanos() as f64 / checks;
+    let new = new.as_nanos() as f64 / checks;
+    println!(
+        "{:>8}: legacy {:>6.1} ns, bitmask {:>6.1} ns per check, {:.1}x",
+        name,
+        old,
+        new,
+        old / new
+    );
+}
+
+fn main() {
+    let mut positions = positions();
+    let candidates = positions.iter().map(|p| p.candidates.len()).sum();
+
+    // every answer has to match before the times mean anything
+    for position in &mut positions {
+        let game = &position.game;
+        let legacy = &mut position.legacy;
+        for (block, old) in &position.candidates {
+            assert_eq!(legacy.bounds_check(old), game.fits(block), "{:?}", block);
+        }
+        let mut fallen = game.get_block().unwrap();
+        fallen.pos.1 += 1;
+        assert_eq!(legacy.reached_bottom(), !game.fits(&fallen), "{:?}", fallen);
+    }
+    println!(
+        "{} positions from {} games, {} moves and turns",
+        positions.len(),
+        GAMES,
+        candidates
+    );
+
+    let old = time(&mut positions, |p| {
+        let legacy = &mut p.legacy;
+        p.candidates
+            .iter()
+            .filter(|(_, old)| legacy.bounds_check(old))
+            .count() as u64
+    });
+    let new = time(&mut positions, |p| {
+        let game = &p.game;
+        p.candidates
+            .iter()
+            .filter(|(block, _)| game.fits(block))
+            .count() as u64
+    });
+    print("bounds", candidates, old, new);
+
+    let old = time(&mut positions, |p| p.legacy.reached_bottom() as u64);
+    let new = time(&mut positions, |p| {
+        let mut fallen = p.game.get_block().unwrap();
+        fallen.pos.1 += 1;
+        !p.game.fits(&fallen) as u64
+    });
+    print("landing", positions.len(), old, new);
+}
@@ -0,0 +1,60 @@
+//! Runs the attract-mode player without a screen, a few games straight on
+//! the engine with a command every tick.
+//!
+//! Usage: `demo [games] [seed]`. Prints each game's lines and pieces and
+//! the board it ended on.
+
+use std::env;
+
+use pinecil_tetris::ai::Ai;
+use pinecil_tetris::game::Tetris;
+
+const DEFAULT_GAMES: u64 = 5;
+
+// Games that get this far are called off, the AI could go on for a while.
+const MAX_PIECES: u32 = 5_000;
+
+fn print_board(game: &Tetris) {
+    for row in &game.get_grid()[..game.height()] {
+        let line: String = (0..game.width())
+            .map(|x| if row & (1 << x) != 0 { '#' } else { '.' })
+            .collect();
+        println!("  {}", line);
+    }
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let games: u64 = args
+        .next()
+        .map(|arg| arg.parse().expect("games"))
+        .unwrap_or(DEFAULT_GAMES);
+    let seed: u64 = args
+        .next()
+        .map(|arg| arg.parse().expect("seed"))
+        .unwrap_or(1);
+
+    let mut game = Tetris::init();
+    for seed in seed..seed + games {
+        game.reset_seeded(seed);
+        let mut ai = Ai::new();
+        while !game.has_ended() && game.get_pieces() < MAX_PIECES {
+            ai.command(&game).apply(&mut game);
+            game.run();
+        }
+
+        let outcome = if game.has_ended() {
+            "topped out"
+        } else {
+            "called off"
+        };
+        println!(
+            "seed {}: {} lines, {} pieces, {}",
+            seed,
+            game.get_score(),
+            game.get_pieces(),
+            outcome
+        );
+        print_board(&game);
+    }
+}
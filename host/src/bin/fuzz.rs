@@ -0,0 +1,26 @@
+//! The long run of the engine fuzz, see `pinecil_tetris_host::fuzz` for
+//! what it checks. `cargo test` runs a short one.
+//!
+//! Usage: `fuzz [ticks] [seed]`. A failure panics with the seed and step
+//! so it can be replayed.
+
+use std::env;
+
+use pinecil_tetris_host::fuzz;
+
+const DEFAULT_TICKS: u64 = 10_000_000;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let ticks: u64 = args
+        .next()
+        .map(|arg| arg.parse().expect("ticks"))
+        .unwrap_or(DEFAULT_TICKS);
+    let seed: u64 = args
+        .next()
+        .map(|arg| arg.parse().expect("seed"))
+        .unwrap_or(1);
+
+    let games = fuzz::run(ticks, seed);
+    println!("{} ticks over {} games, all invariants held", ticks, games);
+}
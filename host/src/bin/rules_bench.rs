@@ -0,0 +1,170 @@
+//! Lets the AI player play the same seeded games under different rules
+//! and prints how it fared as CSV, one row per rule configuration:
+//! lines per game, how many games reached the piece limit and how often
+//! each piece came up.
+//!
+//! The AI gets a command in every `pace` ticks, like a player's reaction
+//! time, so the fall speed of the level matters. The spawn, lock and clear
+//! delays are left out since they only make the AI wait. Wells are as
+//! tall as the standard board, the tolerant clear is one cell short of
+//! the width.
+//!
+//! Usage: `rules_bench [games] [max_pieces] [pace]`.
+
+use std::env;
+use std::thread;
+
+use pinecil_tetris::ai::Ai;
+use pinecil_tetris::game::{Piece, Randomizer, Rules, Tetris, DEFAULT_HEIGHT};
+
+const DEFAULT_GAMES: u64 = 1000;
+const DEFAULT_MAX_PIECES: u32 = 200;
+// about six commands a second, as fast as the demo plays
+const DEFAULT_PACE: u32 = 10;
+
+struct Stats {
+    rules: Rules,
+    pace: u32,
+    games: u64,
+    lines: Vec<u32>,
+    pieces: u64,
+    // games that reached the piece limit without topping out
+    survived: u64,
+    spawned: [u64; 7],
+}
+
+fn configs() -> Vec<Rules> {
+    let mut configs = Vec::new();
+    for randomizer in [Randomizer::Uniform, Randomizer::Bag] {
+        for width in [8, 10] {
+            for tolerance in [1, 0] {
+                for start_level in [0, 9] {
+                    configs.push(Rules {
+                        start_level,
+                        clear_threshold: width as usize - tolerance,
+                        spawn_delay: 0,
+                        lock_delay: 0,
+                        clear_delay: 0,
+                        randomizer,
+                        width,
+                        height: DEFAULT_HEIGHT,
+                    });
+                }
+            }
+        }
+    }
+    configs
+}
+
+fn play(rules: Rules, games: u64, max_pieces: u32, pace: u32) -> Stats {
+    let mut stats = Stats {
+        rules,
+        pace,
+        games,
+        lines: Vec::with_capacity(games as usize),
+        pieces: 0,
+        survived: 0,
+        spawned: [0; 7],
+    };
+
+    let mut game = Tetris::init();
+    game.set_rules(rules);
+    // the same seeds for every configuration
+    for seed in 1..=games {
+        game.reset_seeded(seed);
+        let mut ai = Ai::new();
+        let mut falling = false;
+        let mut wait = 0;
+
+        while !game.has_ended() && game.get_pieces() < max_pieces {
+            if wait == 0 {
+                ai.command(&game).apply(&mut game);
+                wait = pace;
+            }
+            wait -= 1;
+            game.run();
+
+            match game.get_block() {
+                Some(block) if !falling => {
+                    let i = Piece::ALL.iter().position(|&p| p == block.piece).unwrap();
+                    stats.spawned[i] += 1;
+                    falling = true;
+                }
+                Some(_) => {}
+                None => falling = false,
+            }
+        }
+
+        stats.lines.push(game.get_score());
+        stats.pieces += game.get_pieces() as u64;
+        if !game.has_ended() {
+            stats.survived += 1;
+        }
+    }
+    stats
+}
+
+fn print_row(stats: &Stats) {
+    let rules = &stats.rules;
+    let randomizer = match rules.randomizer {
+        Randomizer::Uniform => "uniform",
+        Randomizer::Bag => "bag",
+    };
+    let total_lines: u64 = stats.lines.iter().map(|&l| l as u64).sum();
+    let spawned: u64 = stats.spawned.iter().sum();
+
+    let mut row = format!(
+        "{},{},{},{},{},{},{},{:.2},{},{},{:.3},{:.2}",
+        randomizer,
+        rules.clear_threshold,
+        rules.width,
+        rules.height,
+        rules.start_level,
+        stats.pace,
+        stats.games,
+        total_lines as f64 / stats.games as f64,
+        stats.lines.iter().min().unwrap_or(&0),
+        stats.lines.iter().max().unwrap_or(&0),
+        stats.survived as f64 / stats.games as f64,
+        stats.pieces as f64 / stats.games as f64,
+    );
+    for count in stats.spawned {
+        row += &format!(",{:.4}", count as f64 / spawned.max(1) as f64);
+    }
+    println!("{}", row);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let games: u64 = args
+        .next()
+        .map(|arg| arg.parse().expect("games"))
+        .unwrap_or(DEFAULT_GAMES);
+    let max_pieces: u32 = args
+        .next()
+        .map(|arg| arg.parse().expect("max_pieces"))
+        .unwrap_or(DEFAULT_MAX_PIECES);
+    let pace: u32 = args
+        .next()
+        .map(|arg| arg.parse().expect("pace"))
+        .unwrap_or(DEFAULT_PACE)
+        .max(1);
+
+    // a thread per configuration, the games don't share anything
+    let workers: Vec<_> = configs()
+        .into_iter()
+        .map(|rules| thread::spawn(move || play(rules, games, max_pieces, pace)))
+        .collect();
+
+    let mut header = String::from(
+        "randomizer,clear_threshold,width,height,start_level,pace,games,mean_lines,min_lines,max_lines,survival,mean_pieces",
+    );
+    for piece in Piece::ALL {
+        header += &format!(",{:?}", piece);
+    }
+    println!("{}", header);
+
+    for worker in workers {
+        print_row(&worker.join().expect("worker panicked"));
+    }
+}
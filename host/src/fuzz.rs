@@ -0,0 +1,181 @@
+//! Drives the engine with random input and checks its invariants after
+//! every step:
+//!
+//! - the falling piece stays inside the grid and never overlaps it
+//! - the score never goes down
+//! - the cleared rows the engine reports add up to the score
+//! - the game only ends when a piece locks in the top two rows or has no
+//!   room to spawn
+//! - a saved game loads back as it was, and one with a few bytes changed
+//!   is either turned down or plays on within the same invariants
+//!
+//! A failed check panics with the seed and step, so it can be replayed.
+
+use oorandom::Rand32;
+use pinecil_tetris::game::{
+    Randomizer, Rules, Tetris, MAX_HEIGHT, MAX_WIDTH, MIN_HEIGHT, MIN_WIDTH, STATE_LEN,
+};
+
+// Steps between two checks of the saved game.
+const SAVE_EVERY: u64 = 64;
+
+// Steps a corrupt save that loaded is played on for.
+const CORRUPT_STEPS: u32 = 16;
+
+fn random_rules(rng: &mut Rand32) -> Rules {
+    let width = rng.rand_range(MIN_WIDTH as u32..MAX_WIDTH as u32 + 1);
+    Rules {
+        start_level: rng.rand_range(0..10) as u8,
+        clear_threshold: rng.rand_range(width - 1..width + 1) as usize,
+        spawn_delay: rng.rand_range(0..76) as u8,
+        lock_delay: rng.rand_range(0..31) as u8,
+        clear_delay: rng.rand_range(0..31) as u8,
+        randomizer: if rng.rand_range(0..2) == 0 {
+            Randomizer::Uniform
+        } else {
+            Randomizer::Bag
+        },
+        width: width as u8,
+        height: rng.rand_range(MIN_HEIGHT as u32..MAX_HEIGHT as u32 + 1) as u8,
+    }
+}
+
+fn check(game: &Tetris, seed: u64, step: u64) {
+    if let Some(block) = game.get_block() {
+        for (x, y) in block.cells() {
+            assert!(
+                (0..game.width() as i32).contains(&x) && (0..game.height() as i32).contains(&y),
+                "seed {} step {}: cell ({}, {}) out of bounds",
+                seed,
+                step,
+                x,
+                y
+            );
+        }
+        assert!(
+            game.fits(&block),
+            "seed {} step {}: {:?} overlaps the grid",
+            seed,
+            step,
+            block
+        );
+    }
+
+    for (y, row) in game.get_grid().iter().enumerate() {
+        assert!(
+            row & !game.get_rules().full_row() == 0,
+            "seed {} step {}: row {} has bits outside the well",
+            seed,
+            step,
+            y
+        );
+        assert!(
+            y < game.height() || *row == 0,
+            "seed {} step {}: row {} is below the floor",
+            seed,
+            step,
+            y
+        );
+    }
+}
+
+fn check_save(game: &Tetris, rng: &mut Rand32, seed: u64, step: u64) {
+    let state = game.to_bytes();
+    let restored = Tetris::from_bytes(&state).map(|game| game.to_bytes());
+    assert!(
+        restored == Some(state),
+        "seed {} step {}: the saved game doesn't load back",
+        seed,
+        step
+    );
+
+    let mut corrupt = state;
+    for _ in 0..rng.rand_range(1..4) {
+        corrupt[rng.rand_range(0..STATE_LEN as u32) as usize] = rng.rand_u32() as u8;
+    }
+    if let Some(mut game) = Tetris::from_bytes(&corrupt) {
+        for _ in 0..CORRUPT_STEPS {
+            check(&game, seed, step);
+            game.run();
+        }
+        check(&game, seed, step);
+    }
+}
+
+/// Plays `ticks` steps of random input, dealing every game its own rules
+/// and pieces from `seed`. Returns the number of games played.
+pub fn run(ticks: u64, seed: u64) -> u64 {
+    let mut rng = Rand32::new(seed);
+    let mut game = Tetris::init();
+    game.set_rules(random_rules(&mut rng));
+    game.reset_seeded(seed);
+
+    let mut games = 1;
+    let mut score = 0;
+
+    for step in 0..ticks {
+        match rng.rand_range(0..32) {
+            0..=5 => game.rotate_block(),
+            6 | 7 => game.rotate_block_ccw(),
+            8..=11 => game.move_block(),
+            12 | 13 => game.move_block_left(),
+            14 | 15 => game.move_block_right(),
+            16 => game.drop_block(),
+            _ => {}
+        }
+        check(&game, seed, step);
+        if step % SAVE_EVERY == 0 {
+            check_save(&game, &mut rng, seed, step);
+        }
+
+        let before = game.get_block();
+        let ended_before = game.has_ended();
+        game.run();
+        check(&game, seed, step);
+
+        assert!(
+            game.get_score() >= score,
+            "seed {} step {}: score went down",
+            seed,
+            step
+        );
+        let events = game.take_events();
+        assert_eq!(
+            game.get_score() - score,
+            events.cleared.count_ones(),
+            "seed {} step {}: cleared rows don't match the score",
+            seed,
+            step
+        );
+        assert_eq!(
+            events.ended,
+            game.has_ended() && !ended_before,
+            "seed {} step {}: game over reported at the wrong time",
+            seed,
+            step
+        );
+        score = game.get_score();
+
+        if game.has_ended() {
+            let topped_out = match before {
+                // locked in the top rows
+                Some(block) => block.cells().any(|(_, y)| y <= 1),
+                // the next piece had no room to spawn
+                None => game.get_grid()[..4].iter().any(|row| *row != 0),
+            };
+            assert!(
+                topped_out,
+                "seed {} step {}: game ended without topping out",
+                seed, step
+            );
+
+            // a piece sequence of its own for every game, `reset` would
+            // deal the same one each time
+            game.set_rules(random_rules(&mut rng));
+            game.reset_seeded(rng.rand_u32() as u64);
+            games += 1;
+            score = 0;
+        }
+    }
+    games
+}
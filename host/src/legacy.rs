@@ -0,0 +1,415 @@
+//! The engine as it was before pieces and rows became bitmasks, kept
+//! around to benchmark the rewrite against. Don't fix bugs in here.
+
+use bitvec::{bitarr, order::Lsb0, BitArr};
+use oorandom::Rand32;
+use pinecil_tetris::game::Rules;
+
+// Ticks per cell of fall for each level.
+const GRAVITY: [u8; 10] = [4, 4, 3, 3, 2, 2, 2, 1, 1, 1];
+
+// Lines per level.
+const LEVEL_LINES: u32 = 10;
+
+enum MoveDirection {
+    Left,
+    Right,
+}
+
+#[derive(Clone)]
+pub struct Block {
+    pub shape: [BitArr!(for 4); 4],
+    pub size: u8,
+    pub pos: (i32, i32),
+}
+
+impl Block {
+    pub fn move_left(&mut self) {
+        self.pos.0 -= 1;
+    }
+
+    pub fn move_right(&mut self) {
+        self.pos.0 += 1;
+    }
+
+    pub fn create_square() -> Self {
+        Self {
+            shape: [
+                bitarr![1, 1, 0, 0],
+                bitarr![1, 1, 0, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 2,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_l() -> Self {
+        Self {
+            shape: [
+                bitarr![0, 0, 1, 0],
+                bitarr![1, 1, 1, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 3,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_j() -> Self {
+        Self {
+            shape: [
+                bitarr![1, 0, 0, 0],
+                bitarr![1, 1, 1, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 3,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_z() -> Self {
+        Self {
+            shape: [
+                bitarr![1, 1, 0, 0],
+                bitarr![0, 1, 1, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 3,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_s() -> Self {
+        Self {
+            shape: [
+                bitarr![0, 1, 1, 0],
+                bitarr![1, 1, 0, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 3,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_t() -> Self {
+        Self {
+            shape: [
+                bitarr![0, 1, 0, 0],
+                bitarr![1, 1, 1, 0],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 3,
+            pos: (8 / 2, 1),
+        }
+    }
+
+    pub fn create_i() -> Self {
+        Self {
+            shape: [
+                bitarr![0, 0, 0, 0],
+                bitarr![1, 1, 1, 1],
+                bitarr![0, 0, 0, 0],
+                bitarr![0, 0, 0, 0],
+            ],
+            size: 4,
+            pos: (8 / 2, 2),
+        }
+    }
+}
+
+pub struct Tetris {
+    rules: Rules,
+    current_block: Option<Block>,
+    block_cooldown: u8,
+    gravity_cooldown: u8,
+    grid: [BitArr!(for 8); 32],
+    rng: Rand32,
+    move_direction: MoveDirection,
+    score: u32,
+    pieces: u32,
+    has_ended: bool,
+}
+
+impl Tetris {
+    pub fn init() -> Self {
+        Self {
+            rules: Rules::default(),
+            current_block: None,
+            block_cooldown: 0,
+            gravity_cooldown: 0,
+            grid: [bitarr![0; 8]; 32],
+            rng: Rand32::new(8),
+            move_direction: MoveDirection::Left,
+            score: 0,
+            pieces: 0,
+            has_ended: false,
+        }
+    }
+
+    pub fn add_block(&mut self) {
+        if self.current_block.is_none() {
+            let block = match self.rng.rand_range(0..7) {
+                0 => Block::create_square(),
+                1 => Block::create_l(),
+                2 => Block::create_j(),
+                3 => Block::create_z(),
+                4 => Block::create_s(),
+                5 => Block::create_t(),
+                _ => Block::create_i(),
+            };
+            self.current_block = Some(block);
+        }
+    }
+
+    pub fn rotate_block(&mut self) {
+        if let Some(block) = self.current_block.clone() {
+            let mut rotated_block: Block = block.clone();
+            // the bitarray is rectangular
+            let dim: usize = block.size.into();
+            for i in 0..dim {
+                for j in 0..dim {
+                    rotated_block.shape[j].set(dim - 1 - i, block.shape[i][j]);
+                }
+            }
+            if self.bounds_check(&rotated_block) {
+                self.current_block.replace(rotated_block);
+            }
+        }
+    }
+
+    pub fn bounds_check(&mut self, block: &Block) -> bool {
+        let grid_height: i32 = self.grid.len() as i32;
+        for (i, _) in block.shape.iter().enumerate() {
+            for (j, bit) in block.shape[i].iter().enumerate() {
+                let x = block.pos.0 + j as i32;
+                let y = block.pos.1 + i as i32;
+
+                if *bit {
+                    // x or y out of the bounds of the grid
+                    if !(0..=grid_height).contains(&y) || !(0..=7).contains(&x) {
+                        return false;
+                    }
+
+                    // overlapping with existing grid pixels
+                    if let Some(grid_bit) = self.grid[y as usize].get(x as usize) {
+                        if *grid_bit {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub const fn get_grid(&self) -> [BitArr!(for 8); 32] {
+        self.grid
+    }
+
+    pub fn get_block(&self) -> Option<Block> {
+        self.current_block.clone()
+    }
+
+    pub const fn get_score(&self) -> u32 {
+        self.score
+    }
+
+    pub const fn get_pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    pub fn get_level(&self) -> u8 {
+        let level = self.rules.start_level as u32 + self.score / LEVEL_LINES;
+        level.min(GRAVITY.len() as u32 - 1) as u8
+    }
+
+    /// Puts `block` in the air over `grid`, rows as bitmasks with the
+    /// leftmost column in bit 0, so the checks can be timed on positions
+    /// taken from the bitmask engine.
+    pub fn set_position(&mut self, grid: &[u16], block: Block) {
+        for (row, &bits) in self.grid.iter_mut().zip(grid) {
+            for x in 0..8 {
+                row.set(x, bits & 1 << x != 0);
+            }
+        }
+        self.current_block = Some(block);
+    }
+
+    /// Rules take effect on the next `reset`.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    pub fn reached_bottom(&self) -> bool {
+        if let Some(block) = &self.current_block {
+            for (i, _) in block.shape.iter().enumerate() {
+                for (j, bit) in block.shape[i].iter().enumerate() {
+                    let x = block.pos.0 as usize + j;
+                    let y = block.pos.1 as usize + i;
+                    if y >= (self.grid.len() - 1) {
+                        if *bit {
+                            return true;
+                        }
+                        continue;
+                    } else if let Some(grid_bit) = self.grid[y + 1].get(x) {
+                        if *bit && *grid_bit {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn fall(&mut self) {
+        if let Some(block) = &mut self.current_block {
+            let mut fallen_block: Block = block.clone();
+            fallen_block.pos.1 += 1;
+            self.current_block.replace(fallen_block);
+        }
+    }
+
+    fn block_to_grid(&mut self) {
+        if let Some(block) = &self.current_block {
+            for (i, _) in block.shape.iter().enumerate() {
+                for (j, bit) in block.shape[i].iter().enumerate() {
+                    if *bit {
+                        let x = j + block.pos.0 as usize;
+                        let y = i + block.pos.1 as usize;
+                        self.grid[y].set(x, true);
+                    }
+                }
+            }
+        }
+        self.current_block = None;
+        self.block_cooldown = self.rules.spawn_delay;
+        self.pieces += 1;
+    }
+
+    fn clear_line(&mut self, row: usize) {
+        // iterate rows above cleared row
+        for y in (0..row).rev() {
+            for x in 0..self.grid[y].len() {
+                let mut b: Option<bool> = None;
+                if let Some(bit) = self.grid[y].get(x) {
+                    b = Some(*bit);
+                }
+                // copy value to row below
+                if let Some(bb) = b {
+                    self.grid[y + 1].set(x, bb);
+                }
+                self.grid[y].set(x, false);
+            }
+        }
+    }
+
+    fn check_line_clears(&mut self) {
+        let mut is_clear = true;
+        while is_clear {
+            is_clear = false;
+            let mut clear_row_y = 0;
+            for (y, row) in self.grid.iter().enumerate() {
+                // check if row is clear
+                if row.count_ones() >= self.rules.clear_threshold {
+                    is_clear = true;
+                    clear_row_y = y;
+                    break;
+                }
+            }
+            if is_clear {
+                self.clear_line(clear_row_y);
+                self.score += 1;
+            }
+        }
+    }
+
+    fn clipping_top(&self) -> bool {
+        if let Some(block) = &self.current_block {
+            for (i, _) in block.shape.iter().enumerate() {
+                for (_, bit) in block.shape[i].iter().enumerate() {
+                    let y = block.pos.1 + i as i32;
+                    if y <= 1 && *bit {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    pub fn move_block(&mut self) {
+        if let Some(block) = self.current_block.clone() {
+            let mut moved_block = block;
+            match &self.move_direction {
+                MoveDirection::Left => {
+                    moved_block.move_left();
+                    if !self.bounds_check(&moved_block) {
+                        moved_block.move_right();
+                        moved_block.move_right();
+                        self.move_direction = MoveDirection::Right;
+                    }
+                }
+                MoveDirection::Right => {
+                    moved_block.move_right();
+                    if !self.bounds_check(&moved_block) {
+                        moved_block.move_left();
+                        moved_block.move_left();
+                        self.move_direction = MoveDirection::Left;
+                    }
+                }
+            }
+            self.current_block.replace(moved_block);
+        }
+    }
+
+    pub const fn has_ended(&self) -> bool {
+        self.has_ended
+    }
+
+    pub fn reset(&mut self) {
+        self.current_block = None;
+        self.block_cooldown = 0;
+        self.gravity_cooldown = 0;
+        self.grid = [bitarr![0; 8]; 32];
+        self.rng = Rand32::new(8);
+        self.move_direction = MoveDirection::Left;
+        self.score = 0;
+        self.pieces = 0;
+        self.has_ended = false;
+    }
+
+    pub fn run(&mut self) {
+        if self.has_ended {
+            return;
+        }
+        if self.current_block.is_some() {
+            if self.gravity_cooldown > 0 {
+                self.gravity_cooldown -= 1;
+                return;
+            }
+            self.gravity_cooldown = GRAVITY[self.get_level() as usize] - 1;
+
+            if self.reached_bottom() {
+                if self.clipping_top() {
+                    self.has_ended = true;
+                }
+                self.block_to_grid();
+                self.check_line_clears();
+            } else {
+                self.fall();
+            }
+        } else if self.block_cooldown > 0 {
+            self.block_cooldown -= 1;
+        } else {
+            self.add_block();
+        }
+    }
+}
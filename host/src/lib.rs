@@ -0,0 +1,59 @@
+//! Host-side tools for the game logic: benchmarks and test harnesses that
+//! are too heavy to run on the iron.
+
+pub mod fuzz;
+pub mod legacy;
+
+use std::cell::Cell;
+
+use pinecil_tetris::clock::Clock;
+use pinecil_tetris::supply::{VoltageSensor, ADC_MAX, DIVIDER, VREF_MV};
+
+/// A clock that only moves when told to.
+pub struct FakeClock {
+    now_ms: Cell<u32>,
+}
+
+impl FakeClock {
+    pub fn new(now_ms: u32) -> Self {
+        Self {
+            now_ms: Cell::new(now_ms),
+        }
+    }
+
+    pub fn advance(&self, ms: u32) {
+        self.now_ms.set(self.now_ms.get().wrapping_add(ms));
+    }
+}
+
+impl Clock for FakeClock {
+    fn now_ms(&self) -> u32 {
+        self.now_ms.get()
+    }
+}
+
+/// Stands in for the iron's ADC, reads back whatever input voltage it
+/// was last set to.
+pub struct FakeAdc {
+    raw: u16,
+}
+
+impl FakeAdc {
+    pub fn new(millivolts: u32) -> Self {
+        let mut adc = Self { raw: 0 };
+        adc.set_millivolts(millivolts);
+        adc
+    }
+
+    /// The sample the real divider and ADC would give at `millivolts`.
+    pub fn set_millivolts(&mut self, millivolts: u32) {
+        let raw = (millivolts * ADC_MAX + VREF_MV * DIVIDER / 2) / (VREF_MV * DIVIDER);
+        self.raw = raw.min(ADC_MAX) as u16;
+    }
+}
+
+impl VoltageSensor for FakeAdc {
+    fn read_raw(&mut self) -> u16 {
+        self.raw
+    }
+}
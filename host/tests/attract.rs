@@ -0,0 +1,30 @@
+//! The attract-mode demo starts on an idle title screen, plays and ends
+//! on input.
+
+use pinecil_tetris::app::{App, Screen, ATTRACT_AFTER};
+use pinecil_tetris::input::{Button, Gesture};
+use pinecil_tetris::settings::Settings;
+
+#[test]
+fn demo_starts_and_stops() {
+    let mut app = App::init(Settings::default());
+    for _ in 0..ATTRACT_AFTER - 1 {
+        app.tick();
+    }
+    assert_eq!(app.screen(), Screen::Title, "demo started early");
+    app.tick();
+    assert_eq!(
+        app.screen(),
+        Screen::Demo,
+        "demo didn't start after {} idle ticks",
+        ATTRACT_AFTER
+    );
+
+    for _ in 0..ATTRACT_AFTER {
+        app.tick();
+    }
+    assert!(app.game().get_pieces() > 0, "demo placed no pieces");
+
+    app.handle(Gesture::Press(Button::Plus));
+    assert_eq!(app.screen(), Screen::Title, "input didn't end the demo");
+}
@@ -0,0 +1,97 @@
+//! The short number formats of the small font:
+//!
+//! - a few known numbers and times come out as expected
+//! - every number fits a 16 pixel line, and the halves of one stacked in
+//!   the portrait score box fit inside its border
+//! - a shortened number never shows more than the real one
+
+use pinecil_tetris::digits::{clock, compact, split, width, BUF_LEN};
+
+// A text line, and the inside of the score box in portrait.
+const LINE: i32 = 16;
+const BOX: i32 = 14;
+
+#[test]
+fn examples() {
+    let mut buf = [0u8; BUF_LEN];
+    for (value, expected) in [
+        (0, "0"),
+        (9_999, "9999"),
+        (10_000, "10.0k"),
+        (12_345, "12.3k"),
+        (123_456, "123k"),
+        (999_999, "999k"),
+        (1_234_567, "1.23M"),
+        (u32::MAX, "4.29G"),
+    ] {
+        assert_eq!(compact(value, &mut buf), expected, "{}", value);
+    }
+
+    for (seconds, expected) in [
+        (0, "0:00"),
+        (65, "1:05"),
+        (599, "9:59"),
+        (600, "10m"),
+        (59_999, "999m"),
+        (60_000, "16h"),
+        (u32::MAX, "999h"),
+    ] {
+        assert_eq!(clock(seconds, &mut buf), expected, "{} s", seconds);
+    }
+}
+
+fn check_fits(text: &str) {
+    assert!(
+        width(text) < LINE,
+        "{:?} is {} pixels wide",
+        text,
+        width(text)
+    );
+    if width(text) > BOX {
+        let (top, bottom) = split(text);
+        assert!(
+            !top.is_empty() && width(top) <= BOX && width(bottom) <= BOX,
+            "{:?} stacks as {:?} over {:?}",
+            text,
+            top,
+            bottom
+        );
+    }
+}
+
+// The digits and the suffix back into a number.
+fn shown(text: &str) -> f64 {
+    let (number, unit) = match text.as_bytes()[text.len() - 1] {
+        b'k' => (&text[..text.len() - 1], 1e3),
+        b'M' => (&text[..text.len() - 1], 1e6),
+        b'G' => (&text[..text.len() - 1], 1e9),
+        _ => (text, 1.0),
+    };
+    number.parse::<f64>().expect("digits") * unit
+}
+
+#[test]
+fn numbers_fit_and_never_overstate() {
+    let mut buf = [0u8; BUF_LEN];
+    let mut value: u64 = 0;
+    while value <= u32::MAX as u64 {
+        let text = compact(value as u32, &mut buf);
+        check_fits(text);
+        // a little slack for the float parse
+        assert!(
+            shown(text) <= value as f64 * (1.0 + 1e-9),
+            "{} shows as {:?}",
+            value,
+            text
+        );
+        value += 1 + value / 1_000;
+    }
+}
+
+#[test]
+fn times_fit() {
+    let mut buf = [0u8; BUF_LEN];
+    for seconds in (0..1_000_000).chain([u32::MAX]) {
+        check_fits(clock(seconds, &mut buf));
+    }
+}
@@ -0,0 +1,64 @@
+//! The display double buffer with random fills and transfers:
+//!
+//! - `start` hands out what was last filled in, cut to the buffer size
+//! - a second `start` is turned down until `finish`
+//! - filling the back buffer never touches the one being sent, and the
+//!   last one sent stays readable after `finish`
+
+use oorandom::Rand32;
+use pinecil_tetris::double_buffer::DoubleBuffer;
+
+const N: usize = 16;
+const STEPS: usize = 100_000;
+
+#[test]
+fn random_transfers() {
+    let mut rng = Rand32::new(3);
+    let mut buffers = DoubleBuffer::<N>::new();
+    // what the back buffer was filled with, and what went out last
+    let mut filled = [0u8; N];
+    let mut sent: Vec<u8> = Vec::new();
+
+    assert!(
+        !buffers.is_busy() && buffers.front().is_empty(),
+        "a new buffer has something to send"
+    );
+
+    for step in 0..STEPS {
+        match rng.rand_range(0..3) {
+            0 => {
+                for byte in buffers.back_mut().iter_mut() {
+                    *byte = rng.rand_u32() as u8;
+                }
+                filled = *buffers.back_mut();
+            }
+            1 => {
+                let len = rng.rand_range(0..N as u32 + 4) as usize;
+                let busy = buffers.is_busy();
+                match buffers.start(len) {
+                    Some(_) if busy => panic!("step {}: started twice without finishing", step),
+                    Some(front) => {
+                        assert_eq!(
+                            front,
+                            &filled[..len.min(N)],
+                            "step {}: started something else than was filled in",
+                            step
+                        );
+                        sent = front.to_vec();
+                        // the other buffer is the back one now
+                        filled = *buffers.back_mut();
+                    }
+                    None => assert!(busy, "step {}: turned down while idle", step),
+                }
+            }
+            _ => buffers.finish(),
+        }
+
+        assert_eq!(
+            buffers.front(),
+            sent.as_slice(),
+            "step {}: the buffer being sent changed",
+            step
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Plays random input through the app and sends each frame the way the
+//! display does, only its dirty area and committed one flush late. After
+//! every frame the model panel the areas were copied into has to match a
+//! full redraw, also across orientation changes and display resets.
+
+use oorandom::Rand32;
+use pinecil_tetris::app::App;
+use pinecil_tetris::frame::{Area, Framebuffer, FRAME_LEN, WIDTH};
+use pinecil_tetris::input::{Button, Gesture};
+use pinecil_tetris::layout::Layout;
+use pinecil_tetris::render;
+use pinecil_tetris::settings::Settings;
+
+const FRAMES: u64 = 25_000;
+
+const GESTURES: [Gesture; 9] = [
+    Gesture::Press(Button::Minus),
+    Gesture::Press(Button::Plus),
+    Gesture::Hold(Button::Minus),
+    Gesture::Hold(Button::Plus),
+    Gesture::DoubleTap(Button::Minus),
+    Gesture::DoubleTap(Button::Plus),
+    Gesture::Tap(Button::Minus),
+    Gesture::Tap(Button::Plus),
+    Gesture::Chord,
+];
+
+// The panel memory, and what was sent to it last without being committed.
+struct Panel {
+    memory: [u8; FRAME_LEN],
+    pending: Option<(Area, Vec<u8>)>,
+}
+
+impl Panel {
+    fn settle(&mut self, frame: &mut Framebuffer) {
+        if let Some((area, bytes)) = self.pending.take() {
+            frame.commit_area(&area, &bytes);
+        }
+    }
+
+    // `done` says whether the last transfer is over before the next area
+    // is worked out, as it usually is on the iron.
+    fn flush(&mut self, frame: &mut Framebuffer, done: bool) {
+        if done {
+            self.settle(frame);
+        }
+        let area = match (frame.dirty_area(), &self.pending) {
+            (Some(dirty), Some((pending, _))) => dirty.union(pending),
+            (Some(area), None) => area,
+            (None, Some((pending, _))) => *pending,
+            (None, None) => return,
+        };
+        let mut bytes = vec![0; area.byte_count()];
+        frame.copy_area(&area, &mut bytes);
+        self.settle(frame);
+
+        let width = area.width as usize;
+        for (i, page) in (area.page..area.page + area.pages).enumerate() {
+            let start = page as usize * WIDTH + area.column as usize;
+            self.memory[start..start + width].copy_from_slice(&bytes[i * width..(i + 1) * width]);
+        }
+        self.pending = Some((area, bytes));
+    }
+}
+
+fn check_frames(seed: u64) {
+    let mut rng = Rand32::new(seed);
+    let mut app = App::init(Settings::default());
+    let mut frame = Framebuffer::new(true);
+    // whatever the panel shows after power up
+    let mut panel = Panel {
+        memory: [0; FRAME_LEN],
+        pending: None,
+    };
+    for byte in panel.memory.iter_mut() {
+        *byte = rng.rand_u32() as u8;
+    }
+
+    for n in 0..FRAMES {
+        if rng.rand_range(0..8) == 0 {
+            let gesture = GESTURES[rng.rand_range(0..GESTURES.len() as u32) as usize];
+            app.handle(gesture);
+        }
+        app.tick();
+
+        if let Some(settings) = app.take_saved_settings() {
+            panel.settle(&mut frame);
+            frame.set_transposed(!Layout::new(&settings).is_landscape());
+        }
+        // a display reset drops the transfer in flight and the panel memory
+        if rng.rand_range(0..5000) == 0 {
+            panel.pending = None;
+            for byte in panel.memory.iter_mut() {
+                *byte = rng.rand_u32() as u8;
+            }
+            frame.invalidate();
+        }
+
+        frame.clear_buffer();
+        render::draw(&app, &mut frame).unwrap();
+        panel.flush(&mut frame, rng.rand_range(0..4) != 0);
+
+        assert!(
+            panel.memory == *frame.as_bytes(),
+            "seed {}, frame {}: panel differs from a full redraw on {:?}",
+            seed,
+            n,
+            app.screen()
+        );
+    }
+}
+
+#[test]
+fn dirty_areas_match_a_full_redraw() {
+    for seed in 1..=4 {
+        check_frames(seed);
+    }
+}
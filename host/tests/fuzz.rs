@@ -0,0 +1,11 @@
+//! A short run of the engine fuzz on a few seeds, the `fuzz` binary does
+//! the long one.
+
+use pinecil_tetris_host::fuzz;
+
+#[test]
+fn invariants_hold() {
+    for seed in 1..=4 {
+        assert!(fuzz::run(250_000, seed) > 1);
+    }
+}
@@ -0,0 +1,137 @@
+//! The button timing against a fake clock, so every edge lands on an
+//! exact millisecond:
+//!
+//! - a press is reported on the down edge, bounces right after a release
+//!   are dropped
+//! - a hold is reported once, after `HOLD_MS`
+//! - a second short tap within `DOUBLE_TAP_MS` is a double tap, a third
+//!   one is a plain press again, and holds never start one
+//! - a short tap no second one follows is reported once, `DOUBLE_TAP_MS`
+//!   after its release
+//! - pressing the second button makes a chord and no holds follow
+//! - all of the above across the wrap of the millisecond counter
+//! - `Interval` goes off once per period and doesn't queue up missed ones
+
+use pinecil_tetris::clock::{Clock, Interval};
+use pinecil_tetris::input::{Button, ButtonTracker, Gesture, DOUBLE_TAP_MS, HOLD_MS};
+use pinecil_tetris_host::FakeClock;
+
+const STARTS_MS: [u32; 4] = [0, 12_345, u32::MAX - 700, u32::MAX - 20];
+
+fn check_buttons(start_ms: u32) {
+    let clock = FakeClock::new(start_ms);
+    let mut buttons = ButtonTracker::new();
+
+    // a tracker that has never seen a release still takes the first press
+    let first = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(first, Some(Gesture::Press(Button::Plus)), "first press");
+    clock.advance(50);
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(5);
+    let bounce = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(bounce, None, "bounce 5 ms after release");
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(DOUBLE_TAP_MS);
+    let late = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(
+        late,
+        Some(Gesture::Press(Button::Plus)),
+        "tap after DOUBLE_TAP_MS"
+    );
+    clock.advance(50);
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(DOUBLE_TAP_MS - 1);
+    assert_eq!(
+        buttons.poll(clock.now_ms()),
+        None,
+        "tap before DOUBLE_TAP_MS"
+    );
+    let double = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(double, Some(Gesture::DoubleTap(Button::Plus)), "double tap");
+    clock.advance(50);
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(50);
+    let third = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(third, Some(Gesture::Press(Button::Plus)), "third tap");
+    clock.advance(HOLD_MS);
+    buttons.poll(clock.now_ms());
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(50);
+    let after_hold = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(
+        after_hold,
+        Some(Gesture::Press(Button::Plus)),
+        "tap after a hold"
+    );
+    buttons.update(Button::Plus, false, clock.now_ms());
+
+    clock.advance(DOUBLE_TAP_MS);
+    assert_eq!(
+        buttons.poll(clock.now_ms()),
+        Some(Gesture::Tap(Button::Plus)),
+        "tap at DOUBLE_TAP_MS"
+    );
+    assert_eq!(buttons.poll(clock.now_ms()), None, "tap reported twice");
+    let press = buttons.update(Button::Minus, true, clock.now_ms());
+    assert_eq!(press, Some(Gesture::Press(Button::Minus)), "press");
+
+    clock.advance(HOLD_MS - 1);
+    assert_eq!(buttons.poll(clock.now_ms()), None, "poll before HOLD_MS");
+    clock.advance(1);
+    assert_eq!(
+        buttons.poll(clock.now_ms()),
+        Some(Gesture::Hold(Button::Minus)),
+        "poll at HOLD_MS"
+    );
+    clock.advance(HOLD_MS);
+    assert_eq!(buttons.poll(clock.now_ms()), None, "second hold");
+
+    clock.advance(10);
+    let chord = buttons.update(Button::Plus, true, clock.now_ms());
+    assert_eq!(chord, Some(Gesture::Chord), "second button down");
+    clock.advance(2 * HOLD_MS);
+    assert_eq!(buttons.poll(clock.now_ms()), None, "hold during a chord");
+
+    buttons.update(Button::Plus, false, clock.now_ms());
+    buttons.update(Button::Minus, false, clock.now_ms());
+    clock.advance(100);
+    let after = buttons.update(Button::Minus, true, clock.now_ms());
+    assert_eq!(
+        after,
+        Some(Gesture::Press(Button::Minus)),
+        "press after a chord"
+    );
+}
+
+#[test]
+fn buttons() {
+    for start_ms in STARTS_MS {
+        check_buttons(start_ms);
+    }
+}
+
+#[test]
+fn interval() {
+    for start_ms in STARTS_MS {
+        let clock = FakeClock::new(start_ms);
+        let mut interval = Interval::new(1000, clock.now_ms());
+
+        let mut fired = 0;
+        for _ in 0..10_000 {
+            clock.advance(1);
+            if interval.elapsed(&clock) {
+                fired += 1;
+            }
+        }
+        assert_eq!(fired, 10, "1 s interval in 10 s from {} ms", start_ms);
+
+        clock.advance(5000);
+        let late = (0..2).filter(|_| interval.elapsed(&clock)).count();
+        assert_eq!(late, 1, "went off again after falling behind");
+    }
+}
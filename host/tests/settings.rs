@@ -0,0 +1,427 @@
+//! The stored settings and what the buttons do in a game:
+//!
+//! - every button mapping survives `to_bytes` and `from_bytes`
+//! - every combination of settings survives a flash record, and records
+//!   with a flipped byte or another version are turned down
+//! - every well and cell size is cut down to fit the screen, and the game
+//!   is played on what is shown
+//! - two quick taps of a button without a double tap of its own run its
+//!   press twice, one with a double tap of its own runs only that, and a
+//!   single tap runs once the double tap time is up
+//! - the first button of a chord doesn't run its press before the chord,
+//!   and a press on its own runs once the chord window is up
+//! - switching hands in the menu swaps the buttons only once it is left
+//! - every label and name on the menus fits a line of the screen
+
+use pinecil_tetris::app::{App, GameMode, ResetCause, Screen, CHORD_TICKS};
+use pinecil_tetris::controls::{Command, Mapping, Preset, Slot};
+use pinecil_tetris::game::{MAX_HEIGHT, MAX_WIDTH, MIN_HEIGHT, MIN_WIDTH};
+use pinecil_tetris::input::{Button, Gesture};
+use pinecil_tetris::layout::{Layout, MAX_CELL};
+use pinecil_tetris::render::LABEL_CHARS;
+use pinecil_tetris::settings::{
+    Settings, SettingsItem, BRIGHTNESS_LEVELS, MAX_SPAWN_DELAY, MAX_START_LEVEL, RECORD_LEN,
+    SETTINGS_VERSION,
+};
+
+// Columns, rows and cell width and height: the default, the readable
+// one, a wide one, and both ends.
+const BOARDS: [(u8, u8, u8, u8); 5] = [
+    (8, 32, 2, 2),
+    (5, 21, 3, 5),
+    (10, 32, 1, 2),
+    (MIN_WIDTH as u8, MIN_HEIGHT as u8, MAX_CELL.0, MAX_CELL.1),
+    (MAX_WIDTH as u8, MAX_HEIGHT as u8, 1, 1),
+];
+
+const COMMANDS: [Command; 8] = [
+    Command::Nothing,
+    Command::RotateCw,
+    Command::RotateCcw,
+    Command::Move,
+    Command::MoveLeft,
+    Command::MoveRight,
+    Command::Drop,
+    Command::Pause,
+];
+
+#[test]
+fn mappings_round_trip() {
+    // every command in every slot, counting in base 8
+    for n in 0..COMMANDS.len().pow(Slot::ALL.len() as u32) {
+        let mut mapping = Mapping::default();
+        let mut digits = n;
+        for slot in Slot::ALL {
+            mapping.set(slot, COMMANDS[digits % COMMANDS.len()]);
+            digits /= COMMANDS.len();
+        }
+        assert_eq!(Mapping::from_bytes(&mapping.to_bytes()), Some(mapping));
+    }
+}
+
+fn all_settings() -> Vec<Settings> {
+    let mut all = Vec::new();
+    let mut custom = Preset::LeftRight.mapping();
+    custom.set(Slot::Chord, Command::Drop);
+    let mappings: Vec<Mapping> = Preset::ALL
+        .iter()
+        .map(|preset| preset.mapping())
+        .chain([custom])
+        .collect();
+
+    for brightness in 0..BRIGHTNESS_LEVELS {
+        for start_level in 0..=MAX_START_LEVEL {
+            for &mapping in &mappings {
+                for flags in 0..8 {
+                    for (columns, rows, cell_width, cell_height) in BOARDS {
+                        for spawn_delay in 0..=MAX_SPAWN_DELAY {
+                            all.push(Settings {
+                                brightness,
+                                start_level,
+                                mapping,
+                                left_handed: flags & 1 != 0,
+                                landscape: flags & 2 != 0,
+                                forgiving_clears: flags & 4 != 0,
+                                spawn_delay,
+                                columns,
+                                rows,
+                                cell_width,
+                                cell_height,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    all
+}
+
+#[test]
+fn records_round_trip() {
+    for settings in all_settings() {
+        let record = settings.to_bytes();
+        assert_eq!(Settings::from_bytes(&record), Some(settings));
+
+        for i in 0..RECORD_LEN {
+            let mut corrupt = record;
+            corrupt[i] ^= 0x10;
+            assert!(
+                Settings::from_bytes(&corrupt).is_none(),
+                "byte {} flipped in {:?} still reads",
+                i,
+                settings
+            );
+        }
+        assert!(
+            Settings::from_bytes(&record[..RECORD_LEN - 1]).is_none(),
+            "a short record reads"
+        );
+    }
+
+    let mut old = Settings::default().to_bytes();
+    old[2] = SETTINGS_VERSION - 1;
+    assert!(
+        Settings::from_bytes(&old).is_none(),
+        "a record from the last version reads"
+    );
+}
+
+#[test]
+fn boards_fit_the_screen() {
+    for columns in MIN_WIDTH as u8..=MAX_WIDTH as u8 {
+        for rows in MIN_HEIGHT as u8..=MAX_HEIGHT as u8 {
+            for cell_width in 1..=MAX_CELL.0 {
+                for cell_height in 1..=MAX_CELL.1 {
+                    for landscape in [false, true] {
+                        check_board(Settings {
+                            columns,
+                            rows,
+                            cell_width,
+                            cell_height,
+                            landscape,
+                            ..Settings::default()
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn board_steps_come_around() {
+    // every press in the menu changes what the screen shows, and the
+    // steps come back around to where they started
+    for item in [SettingsItem::Columns, SettingsItem::Rows] {
+        let mut settings = Settings {
+            cell_height: 3,
+            ..Settings::default()
+        };
+        let start = Layout::new(&settings).board();
+        let mut shown = start;
+        for press in 0.. {
+            settings.cycle(item);
+            check_board(settings);
+            let board = Layout::new(&settings).board();
+            assert_ne!(board, shown, "a press of {:?} didn't change the well", item);
+            if board == start {
+                break;
+            }
+            assert!(
+                press <= MAX_WIDTH + MAX_HEIGHT,
+                "{:?} never comes back around",
+                item
+            );
+            shown = board;
+        }
+    }
+    for (item, steps) in [
+        (SettingsItem::CellWidth, MAX_CELL.0),
+        (SettingsItem::CellHeight, MAX_CELL.1),
+    ] {
+        let mut settings = Settings::default();
+        for _ in 0..steps {
+            settings.cycle(item);
+            check_board(settings);
+            assert_eq!(Settings::from_bytes(&settings.to_bytes()), Some(settings));
+        }
+        assert_eq!(
+            settings,
+            Settings::default(),
+            "{:?} doesn't come back around",
+            item
+        );
+    }
+}
+
+// The well of `settings` lies inside the screen, and the game gets its size.
+fn check_board(settings: Settings) {
+    let layout = Layout::new(&settings);
+    let (columns, rows) = layout.board();
+    assert!(
+        (MIN_WIDTH..=MAX_WIDTH).contains(&(columns as usize))
+            && (MIN_HEIGHT..=MAX_HEIGHT).contains(&(rows as usize))
+            && columns <= settings.columns
+            && rows <= settings.rows,
+        "{:?} gets a {}x{} well",
+        settings,
+        columns,
+        rows
+    );
+
+    let (width, height) = layout.cell_size();
+    let (screen_width, screen_height) = if layout.is_landscape() {
+        (96, 16)
+    } else {
+        (16, 96)
+    };
+    for (x, y) in [(0, 0), (columns as i32 - 1, rows as i32 - 1)] {
+        let (left, top) = layout.cell(x, y);
+        assert!(
+            left >= 0
+                && top >= 0
+                && left + width as i32 <= screen_width
+                && top + height as i32 <= screen_height,
+            "cell {},{} of {:?} is off the screen",
+            x,
+            y,
+            settings
+        );
+    }
+
+    let rules = settings.rules();
+    assert_eq!(
+        (rules.width, rules.height),
+        (columns, rows),
+        "{:?} plays on another well than it shows",
+        settings
+    );
+}
+
+// A game of `preset` with a piece in the air.
+fn playing(preset: Preset) -> App {
+    let settings = Settings {
+        mapping: preset.mapping(),
+        ..Settings::default()
+    };
+    let mut app = App::init(settings);
+    app.handle(Gesture::Press(Button::Plus));
+    app.handle(Gesture::Press(Button::Minus));
+    while app.game().get_block().is_none() {
+        app.tick();
+    }
+    assert_eq!(app.screen(), Screen::Playing);
+    app
+}
+
+// The rotation and column of the piece in the air.
+fn piece(app: &App) -> (u8, i32) {
+    let block = app.game().get_block().expect("a piece");
+    (block.rotation, block.pos.0)
+}
+
+#[test]
+fn double_taps() {
+    // - has no double tap in the classic mapping, two quick taps turn twice
+    let mut app = playing(Preset::Classic);
+    let (rotation, _) = piece(&app);
+    app.handle(Gesture::Press(Button::Minus));
+    app.handle(Gesture::DoubleTap(Button::Minus));
+    app.handle(Gesture::Tap(Button::Minus));
+    assert_eq!(
+        piece(&app).0,
+        (rotation + 2) % 4,
+        "a classic double tap of - didn't turn the piece around"
+    );
+
+    // + turns left and ++ moves in the rotate-heavy one
+    let mut app = playing(Preset::RotateHeavy);
+    let (rotation, x) = piece(&app);
+    app.handle(Gesture::Press(Button::Plus));
+    assert_eq!(
+        piece(&app),
+        (rotation, x),
+        "+ ran before it was known not to be a double tap"
+    );
+    app.handle(Gesture::DoubleTap(Button::Plus));
+    assert!(
+        piece(&app) == (rotation, x - 1) || piece(&app) == (rotation, x + 1),
+        "++ didn't just move the piece"
+    );
+
+    let (rotation, x) = piece(&app);
+    app.handle(Gesture::Press(Button::Plus));
+    app.handle(Gesture::Tap(Button::Plus));
+    assert_eq!(
+        piece(&app),
+        ((rotation + 3) % 4, x),
+        "a single tap of + didn't turn the piece left"
+    );
+
+    // - lets the + that was held back go first, then waits out the chord
+    let (rotation, _) = piece(&app);
+    app.handle(Gesture::Press(Button::Plus));
+    app.handle(Gesture::Press(Button::Minus));
+    assert_eq!(piece(&app).0, (rotation + 3) % 4, "+ didn't go ahead of -");
+    wait_out_chord(&mut app);
+    assert_eq!(
+        piece(&app).0,
+        rotation,
+        "+ then - didn't turn left and back right"
+    );
+    app.handle(Gesture::Tap(Button::Plus));
+    assert_eq!(piece(&app).0, rotation, "the tap of + ran it a second time");
+}
+
+fn wait_out_chord(app: &mut App) {
+    for _ in 0..CHORD_TICKS {
+        app.tick();
+    }
+}
+
+#[test]
+fn chords() {
+    // the first button of the pause chord doesn't turn the piece first
+    let mut app = playing(Preset::Classic);
+    let (rotation, _) = piece(&app);
+    app.handle(Gesture::Press(Button::Minus));
+    app.handle(Gesture::Chord);
+    assert_eq!(app.screen(), Screen::Paused, "the chord didn't pause");
+    assert_eq!(
+        piece(&app).0,
+        rotation,
+        "- turned the piece before the chord"
+    );
+    app.handle(Gesture::Chord);
+    assert_eq!(app.screen(), Screen::Playing, "the chord didn't resume");
+    wait_out_chord(&mut app);
+    assert_eq!(
+        piece(&app).0,
+        rotation,
+        "the press the chord called off ran later"
+    );
+
+    // on its own it goes once the chord window is up, and only once
+    app.handle(Gesture::Press(Button::Minus));
+    for _ in 1..CHORD_TICKS {
+        app.tick();
+    }
+    assert_eq!(
+        piece(&app).0,
+        rotation,
+        "- ran before the chord window was up"
+    );
+    app.tick();
+    assert_eq!(
+        piece(&app).0,
+        (rotation + 1) % 4,
+        "- didn't run once the chord window was up"
+    );
+    wait_out_chord(&mut app);
+    assert_eq!(piece(&app).0, (rotation + 1) % 4, "- ran twice");
+}
+
+#[test]
+fn handedness() {
+    let mut app = App::init(Settings::default());
+    app.handle(Gesture::Press(Button::Minus));
+    while app.settings_item() != SettingsItem::Handedness {
+        app.handle(Gesture::Press(Button::Plus));
+    }
+    app.handle(Gesture::Press(Button::Minus));
+    assert!(
+        app.settings().buttons_swapped(),
+        "switching hands didn't take"
+    );
+
+    app.handle(Gesture::Press(Button::Plus));
+    assert_eq!(
+        app.settings_item(),
+        SettingsItem::View,
+        "+ stopped walking through the menu after switching hands"
+    );
+    while app.settings_item() != SettingsItem::Exit {
+        app.handle(Gesture::Press(Button::Plus));
+    }
+    app.handle(Gesture::Press(Button::Minus));
+    assert_eq!(
+        app.screen(),
+        Screen::Title,
+        "- didn't leave the menu after switching hands"
+    );
+
+    app.handle(Gesture::Press(Button::Minus));
+    assert_eq!(
+        app.screen(),
+        Screen::ModeSelect,
+        "the buttons weren't swapped after leaving the menu"
+    );
+}
+
+#[test]
+fn labels_fit() {
+    let mut labels = vec![SettingsItem::Brightness.label()];
+    let mut item = SettingsItem::Brightness.next();
+    while item != SettingsItem::Brightness {
+        labels.push(item.label());
+        item = item.next();
+    }
+    labels.extend(COMMANDS.map(Command::name));
+    labels.extend(Slot::ALL.map(Slot::label));
+    labels.extend(Preset::ALL.map(Preset::name));
+    labels.extend([GameMode::Marathon, GameMode::Sprint].map(GameMode::name));
+    labels.extend(
+        [
+            ResetCause::PowerOn,
+            ResetCause::Pin,
+            ResetCause::Software,
+            ResetCause::Watchdog,
+            ResetCause::LowPower,
+        ]
+        .map(ResetCause::name),
+    );
+    for label in labels {
+        assert!(label.len() <= LABEL_CHARS, "{:?} doesn't fit a line", label);
+    }
+}
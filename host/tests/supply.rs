@@ -0,0 +1,131 @@
+//! The input voltage monitor against a fake ADC: the conversion, the
+//! indicator bars and the brown-out warning:
+//!
+//! - conversions land within one ADC step of the input
+//! - the warning comes on once when the voltage drops, not again while
+//!   it stays low, and only clears above the hysteresis band
+//! - the game is paused and saved when it comes on, and the save resumes
+//!   the same game; going on while it stays low doesn't pause it again
+
+use pinecil_tetris::app::{App, Screen};
+use pinecil_tetris::input::{Button, Gesture};
+use pinecil_tetris::settings::Settings;
+use pinecil_tetris::supply::{
+    to_millivolts, SupplyMonitor, VoltageSensor, ADC_MAX, DIVIDER, LOW_MV, VREF_MV,
+};
+use pinecil_tetris_host::FakeAdc;
+
+// Feeds a steady voltage until the filter has settled, returns whether
+// the warning came on along the way.
+fn settle(monitor: &mut SupplyMonitor<FakeAdc>, millivolts: u32) -> bool {
+    monitor.sensor_mut().set_millivolts(millivolts);
+    (0..64).fold(false, |turned_low, _| monitor.update() || turned_low)
+}
+
+#[test]
+fn conversion() {
+    let step = VREF_MV * DIVIDER / ADC_MAX + 1;
+    for millivolts in (0..=30_000).step_by(7) {
+        let read = to_millivolts(FakeAdc::new(millivolts).read_raw());
+        assert!(
+            read.abs_diff(millivolts) <= step,
+            "{} mV reads as {} mV",
+            millivolts,
+            read
+        );
+    }
+}
+
+#[test]
+fn thresholds() {
+    let mut monitor = SupplyMonitor::new(FakeAdc::new(20_000));
+    assert!(
+        monitor.supply().is_none(),
+        "a reading before the first sample"
+    );
+
+    for (millivolts, bars) in [(20_000, 3), (12_000, 3), (9_000, 2), (5_000, 1)] {
+        assert!(
+            !settle(&mut monitor, millivolts),
+            "warning at {} mV",
+            millivolts
+        );
+        let supply = monitor.supply().unwrap();
+        assert!(
+            !supply.low && supply.bars() == bars,
+            "{} mV shows as {:?}",
+            millivolts,
+            supply
+        );
+    }
+
+    assert!(
+        settle(&mut monitor, LOW_MV - 200),
+        "no warning below the threshold"
+    );
+    assert!(
+        !settle(&mut monitor, LOW_MV - 400),
+        "the warning came on twice"
+    );
+    // inside the hysteresis band it stays on
+    settle(&mut monitor, LOW_MV + 100);
+    assert!(
+        monitor.supply().unwrap().low,
+        "the warning cleared inside the hysteresis band"
+    );
+    settle(&mut monitor, 5_000);
+    assert!(
+        !monitor.supply().unwrap().low,
+        "the warning stayed on at 5 V"
+    );
+}
+
+#[test]
+fn auto_save() {
+    let mut monitor = SupplyMonitor::new(FakeAdc::new(5_000));
+    let mut app = App::init(Settings::default());
+    app.handle(Gesture::Press(Button::Plus));
+    app.handle(Gesture::Press(Button::Minus));
+    for _ in 0..200 {
+        app.tick();
+        if app.game().get_block().is_some() {
+            app.handle(Gesture::Press(Button::Plus));
+        }
+    }
+
+    assert!(settle(&mut monitor, 3_000), "no warning at 3 V");
+    app.set_supply(monitor.supply().unwrap());
+    assert_eq!(
+        app.screen(),
+        Screen::Paused,
+        "the game kept running on a low supply"
+    );
+    let record = app.save().expect("a game to save");
+
+    // going on while it stays low is up to the player
+    app.handle(Gesture::Chord);
+    app.set_supply(monitor.supply().unwrap());
+    assert_eq!(
+        app.screen(),
+        Screen::Playing,
+        "paused again without a new drop"
+    );
+    settle(&mut monitor, 5_000);
+    app.set_supply(monitor.supply().unwrap());
+    settle(&mut monitor, 3_000);
+    app.set_supply(monitor.supply().unwrap());
+    assert_eq!(app.screen(), Screen::Paused, "a second drop didn't pause");
+
+    let mut resumed = App::init(Settings::default());
+    assert!(resumed.resume(&record), "the save doesn't resume");
+    assert_eq!(resumed.screen(), Screen::Paused);
+    assert_eq!(resumed.game().to_bytes(), app.game().to_bytes());
+    assert_eq!(resumed.stats().ticks, app.stats().ticks);
+
+    let mut corrupt = record;
+    corrupt[20] ^= 1;
+    assert!(
+        !App::init(Settings::default()).resume(&corrupt),
+        "a corrupt save resumed"
+    );
+}
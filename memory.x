@@ -1,7 +1,10 @@
 MEMORY
 {
-    FLASH : ORIGIN = 0x08000000, LENGTH = 128K
-    RAM : ORIGIN = 0x20000000, LENGTH = 32K
+    /* the last two 1K pages hold the saved game and the settings, see src/flash.rs */
+    FLASH : ORIGIN = 0x08000000, LENGTH = 126K
+    /* the last 512 bytes survive resets: the game checkpoint (src/checkpoint.rs)
+       and the panic record (src/panic.rs) */
+    RAM : ORIGIN = 0x20000000, LENGTH = 0x7E00
 }
 
 REGION_ALIAS("REGION_TEXT", FLASH);
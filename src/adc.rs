@@ -0,0 +1,71 @@
+use gd32vf103_pac as pac;
+use gd32vf103xx_hal::gpio::{gpioa::PA0, Analog};
+use pinecil_tetris::supply::VoltageSensor;
+
+// The input voltage divider sits on PA0, ADC channel 0.
+const VIN_CHANNEL: u32 = 0;
+
+// ADC0 register bits
+const CTL1_ADCON: u32 = 1 << 0;
+const CTL1_CLB: u32 = 1 << 2;
+const CTL1_RSTCLB: u32 = 1 << 3;
+const CTL1_ETSRC_SOFTWARE: u32 = 0b111 << 17;
+const CTL1_ETERC: u32 = 1 << 20;
+const CTL1_SWRCST: u32 = 1 << 22;
+const STAT_EOC: u32 = 1 << 1;
+// 239.5 cycles, the divider has a high source impedance
+const SAMPT_LONGEST: u32 = 0b111;
+
+// RCU bits: APB2 / 8 keeps the ADC clock at 12 MHz, under its 14 MHz limit
+const APB2EN_ADC0EN: u32 = 1 << 9;
+const CFG0_ADCPSC: u32 = 0b11 << 14;
+const CFG0_ADCPSC_2: u32 = 1 << 28;
+
+/// Single software triggered conversions of the input voltage on ADC0.
+pub struct VinAdc {
+    adc: pac::ADC0,
+    _pin: PA0<Analog>,
+}
+
+impl VinAdc {
+    pub fn new(adc: pac::ADC0, pin: PA0<Analog>) -> Self {
+        unsafe {
+            let rcu = &*pac::RCU::ptr();
+            rcu.cfg0
+                .modify(|r, w| w.bits((r.bits() & !CFG0_ADCPSC_2) | CFG0_ADCPSC));
+            rcu.apb2en.modify(|r, w| w.bits(r.bits() | APB2EN_ADC0EN));
+
+            adc.sampt1
+                .write(|w| w.bits(SAMPT_LONGEST << (3 * VIN_CHANNEL)));
+            // a single conversion in the regular sequence
+            adc.rsq0.write(|w| w.bits(0));
+            adc.rsq2.write(|w| w.bits(VIN_CHANNEL));
+            adc.ctl1
+                .write(|w| w.bits(CTL1_ADCON | CTL1_ETSRC_SOFTWARE | CTL1_ETERC));
+        }
+
+        // calibrate once the ADC has been on for a few clocks
+        for _ in 0..1000 {
+            core::hint::spin_loop();
+        }
+        adc.ctl1
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL1_RSTCLB) });
+        while adc.ctl1.read().bits() & CTL1_RSTCLB != 0 {}
+        adc.ctl1
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL1_CLB) });
+        while adc.ctl1.read().bits() & CTL1_CLB != 0 {}
+
+        Self { adc, _pin: pin }
+    }
+}
+
+impl VoltageSensor for VinAdc {
+    fn read_raw(&mut self) -> u16 {
+        self.adc
+            .ctl1
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL1_SWRCST) });
+        while self.adc.stat.read().bits() & STAT_EOC == 0 {}
+        // reading the data clears EOC
+        self.adc.rdata.read().bits() as u16
+    }
+}
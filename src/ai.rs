@@ -0,0 +1,243 @@
+use crate::controls::Command;
+use crate::game::{Block, Tetris, MAX_HEIGHT};
+
+// Pierre Dellacherie's weights, scaled by 1000 so the search runs in
+// integers, there's no FPU on the iron.
+const LANDING_HEIGHT: i32 = -4_500;
+const ERODED_CELLS: i32 = 3_418;
+const ROW_TRANSITIONS: i32 = -3_218;
+const COLUMN_TRANSITIONS: i32 = -9_349;
+const HOLES: i32 = -7_899;
+const WELLS: i32 = -3_386;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Target {
+    rotation: u8,
+    x: i32,
+}
+
+/// Plays a game the way a player would: picks where the falling piece
+/// should go and returns the commands to get it there, one at a time.
+pub struct Ai {
+    target: Option<Target>,
+    // `Tetris::get_pieces` when the target was picked, a new piece needs
+    // a new one even if the gap between them went unseen
+    planned_at: u32,
+    // rotation and column after the last command, to notice when one
+    // didn't go through
+    last: Option<(u8, i32)>,
+}
+
+impl Ai {
+    pub const fn new() -> Self {
+        Self {
+            target: None,
+            planned_at: 0,
+            last: None,
+        }
+    }
+
+    /// The next command for `game`, `Command::Nothing` between pieces.
+    pub fn command(&mut self, game: &Tetris) -> Command {
+        let block = match game.get_block() {
+            Some(block) => block,
+            None => {
+                self.target = None;
+                self.last = None;
+                return Command::Nothing;
+            }
+        };
+        let target = match self.target {
+            Some(target) if self.planned_at == game.get_pieces() => target,
+            _ => {
+                let target = plan(game, &block);
+                self.target = Some(target);
+                self.planned_at = game.get_pieces();
+                self.last = None;
+                target
+            }
+        };
+
+        // blocked by the stack on the way, take what we've got
+        let now = (block.rotation, block.pos.0);
+        if self.last == Some(now) {
+            return Command::Drop;
+        }
+        self.last = Some(now);
+
+        if block.rotation != target.rotation {
+            Command::RotateCw
+        } else if block.pos.0 < target.x {
+            Command::MoveRight
+        } else if block.pos.0 > target.x {
+            Command::MoveLeft
+        } else {
+            Command::Drop
+        }
+    }
+}
+
+impl Default for Ai {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tries every rotation and column from where `block` is now and keeps the
+// best scoring landing spot. Paths around overhangs aren't checked, the
+// piece is just dropped straight down.
+fn plan(game: &Tetris, block: &Block) -> Target {
+    let mut best = Target {
+        rotation: block.rotation,
+        x: block.pos.0,
+    };
+    let mut best_score = i32::MIN;
+
+    for rotation in 0..4 {
+        for x in -3..game.width() as i32 {
+            let mut placed = *block;
+            placed.rotation = rotation;
+            placed.pos.0 = x;
+            if !game.fits(&placed) {
+                continue;
+            }
+            loop {
+                placed.pos.1 += 1;
+                if !game.fits(&placed) {
+                    placed.pos.1 -= 1;
+                    break;
+                }
+            }
+
+            let score = evaluate(game, &placed);
+            if score > best_score {
+                best_score = score;
+                best = Target { rotation, x };
+            }
+        }
+    }
+    best
+}
+
+fn evaluate(game: &Tetris, placed: &Block) -> i32 {
+    let well = Well {
+        width: game.width() as i32,
+        height: game.height(),
+        full_row: game.get_rules().full_row(),
+    };
+    let mut grid = *game.get_grid();
+    let mut top = well.height as i32;
+    let mut bottom = 0;
+    for (x, y) in placed.cells() {
+        grid[y as usize] |= 1 << x;
+        top = top.min(y);
+        bottom = bottom.max(y);
+    }
+    // in half cells, counted up from the floor
+    let landing_height = 2 * well.height as i32 - top - bottom;
+
+    // clear rows the same way the engine does
+    let threshold = game.get_rules().clear_threshold;
+    let mut cleared = 0;
+    let mut piece_cells = 0;
+    let mut y = well.height;
+    while y > 0 {
+        y -= 1;
+        if grid[y].count_ones() as usize >= threshold {
+            // rows below that cleared moved this one down
+            let row = y as i32 - cleared;
+            piece_cells += placed.cells().filter(|&(_, cy)| cy == row).count() as i32;
+            grid.copy_within(0..y, 1);
+            grid[0] = 0;
+            cleared += 1;
+            // the row above moved down into this one
+            y += 1;
+        }
+    }
+    let eroded = cleared * piece_cells;
+
+    LANDING_HEIGHT * landing_height / 2
+        + ERODED_CELLS * eroded
+        + ROW_TRANSITIONS * well.row_transitions(&grid)
+        + COLUMN_TRANSITIONS * well.column_transitions(&grid)
+        + HOLES * well.holes(&grid)
+        + WELLS * well.wells(&grid)
+}
+
+// The size of the well the features are counted in.
+struct Well {
+    width: i32,
+    height: usize,
+    full_row: u16,
+}
+
+impl Well {
+    fn is_filled(&self, grid: &[u16; MAX_HEIGHT], x: i32, y: usize) -> bool {
+        // the walls count as filled
+        !(0..self.width).contains(&x) || grid[y] & (1 << x) != 0
+    }
+
+    // Filled/empty changes along each row, walls included.
+    fn row_transitions(&self, grid: &[u16; MAX_HEIGHT]) -> i32 {
+        let mut transitions = 0;
+        for y in 0..self.height {
+            for x in 0..=self.width {
+                if self.is_filled(grid, x - 1, y) != self.is_filled(grid, x, y) {
+                    transitions += 1;
+                }
+            }
+        }
+        transitions
+    }
+
+    // Filled/empty changes down each column, the floor counts as filled.
+    fn column_transitions(&self, grid: &[u16; MAX_HEIGHT]) -> i32 {
+        let mut transitions = 0;
+        for x in 0..self.width {
+            let mut above = false;
+            for y in 0..self.height {
+                let filled = self.is_filled(grid, x, y);
+                if filled != above {
+                    transitions += 1;
+                }
+                above = filled;
+            }
+            if !above {
+                transitions += 1;
+            }
+        }
+        transitions
+    }
+
+    // Empty cells with something above them.
+    fn holes(&self, grid: &[u16; MAX_HEIGHT]) -> i32 {
+        let mut covered = 0u16;
+        let mut holes = 0;
+        for &row in &grid[..self.height] {
+            holes += (covered & !row & self.full_row).count_ones() as i32;
+            covered |= row;
+        }
+        holes
+    }
+
+    // Empty cells with both neighbours filled, deeper wells count more:
+    // 1 + 2 + ... + depth for each.
+    fn wells(&self, grid: &[u16; MAX_HEIGHT]) -> i32 {
+        let mut sum = 0;
+        for x in 0..self.width {
+            let mut depth = 0;
+            for y in 0..self.height {
+                if !self.is_filled(grid, x, y)
+                    && self.is_filled(grid, x - 1, y)
+                    && self.is_filled(grid, x + 1, y)
+                {
+                    depth += 1;
+                    sum += depth;
+                } else {
+                    depth = 0;
+                }
+            }
+        }
+        sum
+    }
+}
@@ -0,0 +1,138 @@
+use crate::game::{Block, Events, Rules, Tetris, MAX_HEIGHT, TICK_HZ};
+
+/// Ticks a locked piece blinks for.
+pub const BLINK_TICKS: u8 = (TICK_HZ / 5) as u8;
+/// Ticks the game-over curtain takes per row.
+pub const CURTAIN_ROW_TICKS: u8 = 2;
+
+// Ticks per on or off phase of blinking and flashing.
+const BLINK_PHASE: u8 = 3;
+const FLASH_PHASE: u8 = 4;
+
+#[derive(Clone, Copy)]
+enum Animation {
+    /// Rows flash in the grid they were cleared from, for the clear delay.
+    Clearing {
+        rows: u32,
+        grid: [u16; MAX_HEIGHT],
+        left: u8,
+    },
+    /// The piece that just locked blinks.
+    Blinking { block: Block, left: u8 },
+    /// Rows fill up from the bottom after topping out.
+    Curtain { ticks: u16, rows: u8 },
+}
+
+/// Plays the effects for what happens in a game, started from the
+/// engine's `Events` and stepped once per tick.
+pub struct Animations {
+    current: Option<Animation>,
+}
+
+impl Animations {
+    pub const fn new() -> Self {
+        Self { current: None }
+    }
+
+    /// Starts whatever `events` call for, replacing a running animation.
+    /// Topping out wins over a clear, a clear over a blink. Without a
+    /// clear delay cleared rows just go.
+    pub fn start(&mut self, events: &Events, rules: &Rules) {
+        if events.ended {
+            self.current = Some(Animation::Curtain {
+                ticks: 0,
+                rows: rules.height,
+            });
+        } else if events.cleared != 0 && rules.clear_delay > 0 {
+            self.current = Some(Animation::Clearing {
+                rows: events.cleared,
+                grid: events.before_clear,
+                left: rules.clear_delay,
+            });
+        } else if let (Some(block), 0) = (events.locked, events.cleared) {
+            self.current = Some(Animation::Blinking {
+                block,
+                left: BLINK_TICKS,
+            });
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.current = match self.current {
+            Some(Animation::Clearing { rows, grid, left }) if left > 1 => {
+                Some(Animation::Clearing {
+                    rows,
+                    grid,
+                    left: left - 1,
+                })
+            }
+            Some(Animation::Blinking { block, left }) if left > 1 => Some(Animation::Blinking {
+                block,
+                left: left - 1,
+            }),
+            // the curtain stays down once it's done
+            Some(Animation::Curtain { ticks, rows }) => Some(Animation::Curtain {
+                ticks: ticks.saturating_add(1),
+                rows,
+            }),
+            _ => None,
+        };
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+
+    /// Whether the game-over curtain has filled the whole well.
+    pub fn curtain_done(&self) -> bool {
+        match self.current {
+            Some(Animation::Curtain { ticks, rows }) => {
+                ticks >= rows as u16 * CURTAIN_ROW_TICKS as u16
+            }
+            _ => false,
+        }
+    }
+
+    /// The grid to draw for `game` this tick.
+    pub fn grid(&self, game: &Tetris) -> [u16; MAX_HEIGHT] {
+        let mut grid = *game.get_grid();
+        match self.current {
+            Some(Animation::Clearing {
+                rows,
+                grid: before,
+                left,
+            }) => {
+                grid = before;
+                if (left / FLASH_PHASE) % 2 == 1 {
+                    for (y, row) in grid.iter_mut().enumerate() {
+                        if rows & (1 << y) != 0 {
+                            *row = 0;
+                        }
+                    }
+                }
+            }
+            // off half the time
+            Some(Animation::Blinking { block, left }) if (left / BLINK_PHASE) % 2 == 1 => {
+                for (x, y) in block.cells() {
+                    grid[y as usize] &= !(1 << x);
+                }
+            }
+            Some(Animation::Curtain { ticks, rows }) => {
+                let rows = rows as usize;
+                let filled = (ticks / CURTAIN_ROW_TICKS as u16) as usize;
+                let top = rows - filled.min(rows);
+                for row in &mut grid[top..rows] {
+                    *row = game.get_rules().full_row();
+                }
+            }
+            _ => {}
+        }
+        grid
+    }
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,561 @@
+use crate::ai::Ai;
+use crate::animation::Animations;
+use crate::controls::{Command, Slot};
+use crate::game::{Tetris, STATE_LEN, TICK_HZ};
+use crate::idle::{Idle, Power};
+use crate::input::{Button, Gesture};
+use crate::record::Framing;
+use crate::settings::{Settings, SettingsItem};
+use crate::supply::Supply;
+
+/// Lines to clear to finish a sprint.
+pub const SPRINT_LINES: u32 = 20;
+
+/// Ticks on the title screen without input before the demo starts.
+pub const ATTRACT_AFTER: u32 = 10 * TICK_HZ;
+
+// Ticks between the demo player's commands, slow enough to follow.
+const DEMO_PACE: u32 = TICK_HZ / 6;
+
+/// Ticks a press waits for the other button when the chord means
+/// something, both are never pressed quite at once.
+pub const CHORD_TICKS: u32 = TICK_HZ / 15;
+
+pub const SAVE_VERSION: u8 = 4;
+
+// The mode, the ticks played and the game.
+const SAVE_FRAMING: Framing = Framing::new(*b"PG", SAVE_VERSION, 5 + STATE_LEN);
+
+/// Size of a saved game in flash.
+pub const SAVE_LEN: usize = SAVE_FRAMING.size();
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// Play until topping out.
+    Marathon,
+    /// Clear `SPRINT_LINES` lines as fast as possible.
+    Sprint,
+}
+
+impl GameMode {
+    pub const fn next(self) -> Self {
+        match self {
+            GameMode::Marathon => GameMode::Sprint,
+            GameMode::Sprint => GameMode::Marathon,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Mara",
+            GameMode::Sprint => "Sprt",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Screen {
+    Title,
+    Settings,
+    ModeSelect,
+    Playing,
+    Paused,
+    GameOver,
+    Diagnostics,
+    /// Editing which command each gesture runs.
+    Mapping,
+    /// The computer plays while nobody does, any input ends it.
+    Demo,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    pub mode: GameMode,
+    pub score: u32,
+    pub pieces: u32,
+    /// Game ticks spent playing.
+    pub ticks: u32,
+    /// Whether the mode's goal was reached instead of topping out.
+    pub cleared: bool,
+}
+
+impl Stats {
+    const fn new(mode: GameMode) -> Self {
+        Self {
+            mode,
+            score: 0,
+            pieces: 0,
+            ticks: 0,
+            cleared: false,
+        }
+    }
+}
+
+/// What caused the last reset, as the MCU reports it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ResetCause {
+    #[default]
+    PowerOn,
+    /// The reset pin.
+    Pin,
+    /// The firmware asked for it, e.g. after a crash.
+    Software,
+    /// The watchdog wasn't fed in time.
+    Watchdog,
+    LowPower,
+}
+
+impl ResetCause {
+    pub const fn name(self) -> &'static str {
+        match self {
+            ResetCause::PowerOn => "Pwr",
+            ResetCause::Pin => "Pin",
+            ResetCause::Software => "Sw",
+            ResetCause::Watchdog => "WDog",
+            ResetCause::LowPower => "LoP",
+        }
+    }
+}
+
+/// Counters for things that went wrong and were recovered from, shown on
+/// the diagnostics screen. Filled in by the firmware.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Diagnostics {
+    /// Failed display transfers.
+    pub display_errors: u32,
+    /// Times the display was reset and set up again.
+    pub display_resets: u32,
+    pub reset_cause: ResetCause,
+}
+
+/// Everything above the game itself: which screen is shown and
+/// what the buttons do on it.
+pub struct App {
+    screen: Screen,
+    mode: GameMode,
+    game: Tetris,
+    stats: Stats,
+    settings: Settings,
+    settings_item: SettingsItem,
+    // index into `Slot::ALL` in the mapping editor
+    mapping_slot: usize,
+    // a press waiting to be told it wasn't half of a double tap or a chord
+    held_back: Option<Button>,
+    // ticks left until a press held back for the chord goes ahead, zero
+    // while it waits for its tap instead
+    chord_wait: u32,
+    settings_changed: bool,
+    // follows the settings once they are saved, so + and - don't trade
+    // places halfway through the menu
+    buttons_swapped: bool,
+    diagnostics: Diagnostics,
+    idle: Idle,
+    supply: Option<Supply>,
+    animations: Animations,
+    ai: Ai,
+    // demo games played, seeds the next one so they differ
+    demo_games: u64,
+    // ticks until the demo player's next command
+    demo_wait: u32,
+}
+
+impl App {
+    pub fn init(settings: Settings) -> Self {
+        Self {
+            screen: Screen::Title,
+            mode: GameMode::Marathon,
+            game: Tetris::init(),
+            stats: Stats::new(GameMode::Marathon),
+            settings,
+            settings_item: SettingsItem::Brightness,
+            mapping_slot: 0,
+            held_back: None,
+            chord_wait: 0,
+            settings_changed: false,
+            buttons_swapped: settings.buttons_swapped(),
+            diagnostics: Diagnostics::default(),
+            idle: Idle::new(),
+            supply: None,
+            animations: Animations::new(),
+            ai: Ai::new(),
+            demo_games: 0,
+            demo_wait: 0,
+        }
+    }
+
+    pub const fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub const fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub const fn game(&self) -> &Tetris {
+        &self.game
+    }
+
+    pub const fn animations(&self) -> &Animations {
+        &self.animations
+    }
+
+    /// Stats of the current game, or of the last one on the game over screen.
+    pub const fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    pub const fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The settings menu entry under the cursor.
+    pub const fn settings_item(&self) -> SettingsItem {
+        self.settings_item
+    }
+
+    /// The gesture being edited in the mapping editor.
+    pub const fn mapping_slot(&self) -> Slot {
+        Slot::ALL[self.mapping_slot]
+    }
+
+    /// What the panel should be doing, depending on how long nothing happened.
+    pub const fn power(&self) -> Power {
+        self.idle.power()
+    }
+
+    /// Step of the burn-in shift of the HUD, see `Layout::shifted`.
+    pub const fn hud_shift(&self) -> u8 {
+        self.idle.shift()
+    }
+
+    /// The last input voltage reading, if there is a monitor.
+    pub const fn supply(&self) -> Option<Supply> {
+        self.supply
+    }
+
+    /// A running game is paused when the voltage turns low, the caller
+    /// saves it. Only the turn counts, a player who goes on while it stays
+    /// low isn't paused again on every reading.
+    pub fn set_supply(&mut self, supply: Supply) {
+        let was_low = matches!(self.supply, Some(last) if last.low);
+        if supply.low && !was_low && self.screen == Screen::Playing {
+            self.screen = Screen::Paused;
+        }
+        self.supply = Some(supply);
+    }
+
+    /// The game in progress as a record to keep in flash, `None` if there
+    /// is nothing worth saving.
+    pub fn save(&self) -> Option<[u8; SAVE_LEN]> {
+        if !matches!(self.screen, Screen::Playing | Screen::Paused) || self.game.has_ended() {
+            return None;
+        }
+
+        let mut record = [0u8; SAVE_LEN];
+        let payload = SAVE_FRAMING.payload_mut(&mut record);
+        payload[0] = self.mode as u8;
+        payload[1..5].copy_from_slice(&self.stats.ticks.to_le_bytes());
+        payload[5..5 + STATE_LEN].copy_from_slice(&self.game.to_bytes());
+        SAVE_FRAMING.seal(&mut record);
+        Some(record)
+    }
+
+    /// Continues a game from a record made by `save`, paused so the player can pick it up.
+    /// Returns `false` and leaves everything as is if the record is blank,
+    /// corrupt or from another version.
+    pub fn resume(&mut self, record: &[u8]) -> bool {
+        let payload = match SAVE_FRAMING.open(record) {
+            Some(payload) => payload,
+            None => return false,
+        };
+        let mode = match payload[0] {
+            0 => GameMode::Marathon,
+            1 => GameMode::Sprint,
+            _ => return false,
+        };
+        let game = match Tetris::from_bytes(&payload[5..5 + STATE_LEN]) {
+            Some(game) if !game.has_ended() => game,
+            _ => return false,
+        };
+
+        self.mode = mode;
+        self.stats = Stats {
+            mode,
+            score: game.get_score(),
+            pieces: game.get_pieces(),
+            ticks: u32::from_le_bytes([payload[1], payload[2], payload[3], payload[4]]),
+            cleared: false,
+        };
+        self.game = game;
+        self.held_back = None;
+        self.animations.clear();
+        self.screen = Screen::Paused;
+        true
+    }
+
+    pub const fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    pub fn diagnostics_mut(&mut self) -> &mut Diagnostics {
+        &mut self.diagnostics
+    }
+
+    /// Returns the settings once after they were saved from the menu,
+    /// so they can be written to flash and applied.
+    pub fn take_saved_settings(&mut self) -> Option<Settings> {
+        if self.settings_changed {
+            self.settings_changed = false;
+            return Some(self.settings);
+        }
+        None
+    }
+
+    pub fn handle(&mut self, gesture: Gesture) {
+        // the first input after a while only wakes the screen up, the
+        // player can't see what it would do
+        if self.idle.wake() {
+            return;
+        }
+
+        // Only the grip swaps the buttons, the layout doesn't: the well
+        // falls along the long side of the panel in both, the side the two
+        // buttons sit along. Neither button is ever to the left or right of
+        // the well, so landscape keeps the mapping as it is.
+        let gesture = if self.buttons_swapped {
+            gesture.swapped()
+        } else {
+            gesture
+        };
+        // menus don't know double taps, the second tap is just another press
+        let gesture = match gesture {
+            Gesture::DoubleTap(button) if self.screen != Screen::Playing => Gesture::Press(button),
+            gesture => gesture,
+        };
+
+        match self.screen {
+            Screen::Title => match gesture {
+                Gesture::Press(Button::Plus) => self.screen = Screen::ModeSelect,
+                Gesture::Press(Button::Minus) => {
+                    self.settings_item = SettingsItem::Brightness;
+                    self.screen = Screen::Settings;
+                }
+                _ => {}
+            },
+            // + walks through the entries, - changes the value
+            Screen::Settings => match gesture {
+                Gesture::Press(Button::Plus) => self.settings_item = self.settings_item.next(),
+                Gesture::Press(Button::Minus) => match self.settings_item {
+                    SettingsItem::Exit => {
+                        self.settings_changed = true;
+                        self.buttons_swapped = self.settings.buttons_swapped();
+                        self.screen = Screen::Title;
+                    }
+                    SettingsItem::Mapping => {
+                        self.mapping_slot = 0;
+                        self.screen = Screen::Mapping;
+                    }
+                    SettingsItem::Diagnostics => self.screen = Screen::Diagnostics,
+                    item => self.settings.cycle(item),
+                },
+                _ => {}
+            },
+            // + walks through the gestures and back out, - changes the command
+            Screen::Mapping => match gesture {
+                Gesture::Press(Button::Plus) => {
+                    self.mapping_slot += 1;
+                    if self.mapping_slot == Slot::ALL.len() {
+                        self.mapping_slot = 0;
+                        self.screen = Screen::Settings;
+                    }
+                }
+                Gesture::Press(Button::Minus) => {
+                    let slot = self.mapping_slot();
+                    let command = self.settings.mapping.get(slot).next();
+                    self.settings.mapping.set(slot, command);
+                }
+                _ => {}
+            },
+            Screen::Diagnostics => {
+                if let Gesture::Press(_) = gesture {
+                    self.screen = Screen::Settings;
+                }
+            }
+            Screen::ModeSelect => match gesture {
+                Gesture::Press(Button::Plus) => self.mode = self.mode.next(),
+                Gesture::Press(Button::Minus) => self.start(),
+                _ => {}
+            },
+            // the curtain is coming down, nothing to play anymore
+            Screen::Playing if self.game.has_ended() => {}
+            Screen::Playing => self.play(gesture),
+            Screen::Demo => self.screen = Screen::Title,
+            // nothing runs while paused, so gravity and the spawn
+            // delay pick up exactly where they were left. The chord always
+            // resumes, in case the mapping has lost its pause.
+            Screen::Paused => {
+                if gesture == Gesture::Chord
+                    || self.settings.mapping.command(gesture) == Command::Pause
+                {
+                    self.screen = Screen::Playing;
+                }
+            }
+            Screen::GameOver => {
+                if let Gesture::Press(_) = gesture {
+                    self.screen = Screen::Title;
+                }
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.idle.tick(self.screen == Screen::Playing);
+
+        match self.screen {
+            Screen::Title
+                if self.idle.idle_ticks() >= ATTRACT_AFTER && self.power() != Power::Off =>
+            {
+                self.start_demo();
+                return;
+            }
+            Screen::Demo => {
+                self.run_demo();
+                return;
+            }
+            Screen::Playing => {}
+            _ => return,
+        }
+
+        if self.chord_wait > 0 {
+            self.chord_wait -= 1;
+            if let (0, Some(button)) = (self.chord_wait, self.held_back) {
+                self.held_back = None;
+                self.run(self.settings.mapping.command(Gesture::Press(button)));
+                if self.screen != Screen::Playing {
+                    return;
+                }
+            }
+        }
+
+        self.animations.tick();
+        // topped out, the score shows once the curtain is down
+        if self.game.has_ended() {
+            if self.animations.curtain_done() {
+                self.screen = Screen::GameOver;
+            }
+            return;
+        }
+
+        self.game.run();
+        self.animate();
+        self.stats.ticks += 1;
+        self.stats.score = self.game.get_score();
+        self.stats.pieces = self.game.get_pieces();
+
+        if self.mode == GameMode::Sprint && self.stats.score >= SPRINT_LINES {
+            self.stats.cleared = true;
+            self.screen = Screen::GameOver;
+        }
+    }
+
+    fn animate(&mut self) {
+        let events = self.game.take_events();
+        self.animations.start(&events, &self.game.get_rules());
+    }
+
+    // A press of a button with a double tap of its own is held back until
+    // its `Tap` says no second one came, so the two don't both run. A hold
+    // or double tap of the same button, or the chord, calls the press off,
+    // anything from the other button lets it go first.
+    //
+    // Any other press waits `CHORD_TICKS` while the chord is mapped, so the
+    // first button of a chord doesn't run its own command before it. Only
+    // the chord calls that one off, anything else lets it go first.
+    fn play(&mut self, gesture: Gesture) {
+        let mapping = self.settings.mapping;
+        if let Some(earlier) = self.held_back {
+            match gesture {
+                Gesture::Tap(button) if button == earlier => {
+                    self.held_back = None;
+                    self.run(mapping.command(Gesture::Press(earlier)));
+                    return;
+                }
+                Gesture::Tap(_) => return,
+                _ => {
+                    self.held_back = None;
+                    let same_button =
+                        mapping.waits_for_tap(earlier) && gesture.button() == Some(earlier);
+                    if gesture != Gesture::Chord && !same_button {
+                        self.run(mapping.command(Gesture::Press(earlier)));
+                        if self.screen != Screen::Playing {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+
+        match gesture {
+            Gesture::Press(button) if mapping.waits_for_tap(button) => {
+                self.held_back = Some(button);
+                self.chord_wait = 0;
+            }
+            Gesture::Press(button) if mapping.get(Slot::Chord) != Command::Nothing => {
+                self.held_back = Some(button);
+                self.chord_wait = CHORD_TICKS;
+            }
+            gesture => self.run(mapping.command(gesture)),
+        }
+    }
+
+    fn run(&mut self, command: Command) {
+        match command {
+            Command::Pause => self.screen = Screen::Paused,
+            command => command.apply(&mut self.game),
+        }
+    }
+
+    fn start_demo(&mut self) {
+        self.game.set_rules(self.settings.rules());
+        self.game.reset_seeded(self.demo_games);
+        self.demo_games += 1;
+        self.demo_wait = DEMO_PACE;
+        self.ai = Ai::new();
+        self.animations.clear();
+        self.screen = Screen::Demo;
+    }
+
+    fn run_demo(&mut self) {
+        // nobody is watching a dark panel
+        if self.power() == Power::Off {
+            self.screen = Screen::Title;
+            return;
+        }
+
+        self.animations.tick();
+        if self.game.has_ended() {
+            if self.animations.curtain_done() {
+                self.start_demo();
+            }
+            return;
+        }
+
+        if self.demo_wait == 0 {
+            self.ai.command(&self.game).apply(&mut self.game);
+            self.demo_wait = DEMO_PACE;
+        } else {
+            self.demo_wait -= 1;
+        }
+        self.game.run();
+        self.animate();
+    }
+
+    fn start(&mut self) {
+        self.game.set_rules(self.settings.rules());
+        self.game.reset();
+        self.held_back = None;
+        self.animations.clear();
+        self.stats = Stats::new(self.mode);
+        self.screen = Screen::Playing;
+    }
+}
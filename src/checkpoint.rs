@@ -0,0 +1,39 @@
+use core::ptr;
+
+use pinecil_tetris::app::SAVE_LEN;
+
+// Just below the panic record, kept out of RAM in memory.x like it.
+const CHECKPOINT_ADDR: usize = 0x2000_7E00;
+const CHECKPOINT_MAGIC: u32 = 0x4348_4B50;
+
+#[repr(C)]
+struct Checkpoint {
+    magic: u32,
+    record: [u8; SAVE_LEN],
+}
+
+/// Keeps `record` in RAM that survives a reset. Cheap enough to call every
+/// second, unlike writing flash.
+pub fn store(record: &[u8; SAVE_LEN]) {
+    let checkpoint = Checkpoint {
+        magic: CHECKPOINT_MAGIC,
+        record: *record,
+    };
+    unsafe { ptr::write_volatile(CHECKPOINT_ADDR as *mut Checkpoint, checkpoint) };
+}
+
+/// Drops the checkpoint, for when there is no game to come back to.
+pub fn clear() {
+    unsafe { ptr::write_volatile(CHECKPOINT_ADDR as *mut u32, 0) };
+}
+
+/// The last checkpoint from before the reset, if there is one. The record
+/// carries its own checksum, `App::resume` checks that.
+pub fn take() -> Option<[u8; SAVE_LEN]> {
+    let checkpoint = unsafe { ptr::read_volatile(CHECKPOINT_ADDR as *const Checkpoint) };
+    clear();
+    if checkpoint.magic != CHECKPOINT_MAGIC {
+        return None;
+    }
+    Some(checkpoint.record)
+}
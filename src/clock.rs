@@ -0,0 +1,37 @@
+/// A monotonic millisecond clock, wrapping around after about 49 days.
+///
+/// The firmware reads the core timer, the host tools use a fake they advance
+/// by hand, so anything timed against it can be tested step by step.
+pub trait Clock {
+    fn now_ms(&self) -> u32;
+
+    /// Milliseconds since `then_ms`, right across the wrap.
+    fn since(&self, then_ms: u32) -> u32 {
+        self.now_ms().wrapping_sub(then_ms)
+    }
+}
+
+/// Goes off every `period_ms`, for periodic work in a polling loop.
+pub struct Interval {
+    period_ms: u32,
+    last_ms: u32,
+}
+
+impl Interval {
+    pub const fn new(period_ms: u32, now_ms: u32) -> Self {
+        Self {
+            period_ms,
+            last_ms: now_ms,
+        }
+    }
+
+    /// Whether a period has passed since it last went off. A loop that
+    /// fell behind gets one call, not one per missed period.
+    pub fn elapsed<C: Clock>(&mut self, clock: &C) -> bool {
+        if clock.since(self.last_ms) < self.period_ms {
+            return false;
+        }
+        self.last_ms = clock.now_ms();
+        true
+    }
+}
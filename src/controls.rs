@@ -0,0 +1,244 @@
+use crate::game::Tetris;
+use crate::input::{Button, Gesture};
+
+/// What a gesture does in a game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Nothing,
+    RotateCw,
+    RotateCcw,
+    /// Steps the piece sideways, turning around at the walls.
+    Move,
+    MoveLeft,
+    MoveRight,
+    /// Drops the piece onto the stack, it locks on the next frame.
+    Drop,
+    Pause,
+}
+
+impl Command {
+    // new commands go at the end, the index is what's stored
+    const ALL: [Command; 8] = [
+        Command::Nothing,
+        Command::RotateCw,
+        Command::RotateCcw,
+        Command::Move,
+        Command::Drop,
+        Command::Pause,
+        Command::MoveLeft,
+        Command::MoveRight,
+    ];
+
+    /// Runs the command on `game`. Pausing is up to the caller, it does
+    /// nothing here.
+    pub fn apply(self, game: &mut Tetris) {
+        match self {
+            Command::Nothing | Command::Pause => {}
+            Command::RotateCw => game.rotate_block(),
+            Command::RotateCcw => game.rotate_block_ccw(),
+            Command::Move => game.move_block(),
+            Command::MoveLeft => game.move_block_left(),
+            Command::MoveRight => game.move_block_right(),
+            Command::Drop => game.drop_block(),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&c| c == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Command::Nothing => "--",
+            Command::RotateCw => "RotR",
+            Command::RotateCcw => "RotL",
+            Command::Move => "Move",
+            Command::MoveLeft => "Left",
+            Command::MoveRight => "Rght",
+            Command::Drop => "Drop",
+            Command::Pause => "Paus",
+        }
+    }
+
+    fn to_bits(self) -> u8 {
+        Self::ALL.iter().position(|&c| c == self).unwrap_or(0) as u8
+    }
+
+    fn from_bits(bits: u8) -> Option<Self> {
+        Self::ALL.get(bits as usize).copied()
+    }
+}
+
+/// A gesture that can be given a command.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    Press(Button),
+    Hold(Button),
+    DoubleTap(Button),
+    Chord,
+}
+
+impl Slot {
+    /// In the order the mapping editor walks through them.
+    pub const ALL: [Slot; 7] = [
+        Slot::Press(Button::Minus),
+        Slot::Press(Button::Plus),
+        Slot::Hold(Button::Minus),
+        Slot::Hold(Button::Plus),
+        Slot::DoubleTap(Button::Minus),
+        Slot::DoubleTap(Button::Plus),
+        Slot::Chord,
+    ];
+
+    const fn index(self) -> usize {
+        match self {
+            Slot::Press(Button::Minus) => 0,
+            Slot::Press(Button::Plus) => 1,
+            Slot::Hold(Button::Minus) => 2,
+            Slot::Hold(Button::Plus) => 3,
+            Slot::DoubleTap(Button::Minus) => 4,
+            Slot::DoubleTap(Button::Plus) => 5,
+            Slot::Chord => 6,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Slot::Press(Button::Minus) => "-",
+            Slot::Press(Button::Plus) => "+",
+            Slot::Hold(Button::Minus) => "-Hld",
+            Slot::Hold(Button::Plus) => "+Hld",
+            Slot::DoubleTap(Button::Minus) => "--",
+            Slot::DoubleTap(Button::Plus) => "++",
+            Slot::Chord => "+&-",
+        }
+    }
+}
+
+/// Bytes a mapping takes in the settings record, a nibble per slot.
+pub const MAPPING_LEN: usize = 4;
+
+/// Which command each gesture runs while playing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Mapping {
+    commands: [Command; 7],
+}
+
+impl Mapping {
+    /// A double tap without a command of its own is just another press,
+    /// so quick taps aren't lost. A `Tap` does nothing, it only tells
+    /// whether a held back press was a double tap after all.
+    pub const fn command(&self, gesture: Gesture) -> Command {
+        let slot = match gesture {
+            Gesture::DoubleTap(button) if !self.waits_for_tap(button) => Slot::Press(button),
+            Gesture::DoubleTap(button) => Slot::DoubleTap(button),
+            Gesture::Press(button) => Slot::Press(button),
+            Gesture::Hold(button) => Slot::Hold(button),
+            Gesture::Chord => Slot::Chord,
+            Gesture::Tap(_) => return Command::Nothing,
+        };
+        self.commands[slot.index()]
+    }
+
+    /// Whether presses of `button` have to wait for its `Tap`, since a
+    /// double tap means something else than two presses.
+    pub const fn waits_for_tap(&self, button: Button) -> bool {
+        !matches!(self.get(Slot::DoubleTap(button)), Command::Nothing)
+    }
+
+    pub const fn get(&self, slot: Slot) -> Command {
+        self.commands[slot.index()]
+    }
+
+    pub fn set(&mut self, slot: Slot, command: Command) {
+        self.commands[slot.index()] = command;
+    }
+
+    /// Whether any gesture runs `command`.
+    pub fn contains(&self, command: Command) -> bool {
+        self.commands.contains(&command)
+    }
+
+    /// The preset this mapping is, `None` once it has been edited.
+    pub fn preset(&self) -> Option<Preset> {
+        Preset::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.mapping() == *self)
+    }
+
+    pub fn to_bytes(&self) -> [u8; MAPPING_LEN] {
+        let mut bytes = [0u8; MAPPING_LEN];
+        for (i, command) in self.commands.iter().enumerate() {
+            bytes[i / 2] |= command.to_bits() << (4 * (i % 2));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut mapping = Preset::Classic.mapping();
+        for (i, command) in mapping.commands.iter_mut().enumerate() {
+            *command = Command::from_bits(bytes.get(i / 2)? >> (4 * (i % 2)) & 0xF)?;
+        }
+        Some(mapping)
+    }
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Preset::Classic.mapping()
+    }
+}
+
+/// Ready-made mappings to start from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    /// - rotates, + moves, both pause. Nothing waits for double taps.
+    Classic,
+    /// Everything on +, the thumb never has to switch buttons.
+    OneHanded,
+    /// Both directions of rotation a tap away.
+    RotateHeavy,
+    /// - steps left and + right, holding them rotates and drops.
+    LeftRight,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [
+        Preset::Classic,
+        Preset::OneHanded,
+        Preset::RotateHeavy,
+        Preset::LeftRight,
+    ];
+
+    pub const fn next(self) -> Self {
+        match self {
+            Preset::Classic => Preset::OneHanded,
+            Preset::OneHanded => Preset::RotateHeavy,
+            Preset::RotateHeavy => Preset::LeftRight,
+            Preset::LeftRight => Preset::Classic,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Preset::Classic => "Std",
+            Preset::OneHanded => "1Hnd",
+            Preset::RotateHeavy => "Rot",
+            Preset::LeftRight => "L/R",
+        }
+    }
+
+    // Slots in `Slot::ALL` order: - and + press, hold, double tap, chord.
+    pub const fn mapping(self) -> Mapping {
+        use Command::*;
+        let commands = match self {
+            Preset::Classic => [RotateCw, Move, Nothing, Drop, Nothing, Nothing, Pause],
+            Preset::OneHanded => [Pause, Move, Nothing, Drop, Nothing, RotateCw, Pause],
+            Preset::RotateHeavy => [RotateCw, RotateCcw, Drop, Move, Nothing, Move, Pause],
+            Preset::LeftRight => [MoveLeft, MoveRight, RotateCw, Drop, Nothing, Nothing, Pause],
+        };
+        Mapping { commands }
+    }
+}
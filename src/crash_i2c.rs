@@ -0,0 +1,118 @@
+use core::sync::atomic::Ordering;
+
+use embedded_hal::blocking::i2c::Write;
+use gd32vf103_pac as pac;
+
+use crate::APB1_HZ;
+
+// Standard mode, the crash screen is drawn once and speed doesn't matter.
+const BUS_HZ: u32 = 100_000;
+
+// Status polls before a transfer is given up on, a few milliseconds.
+const TIMEOUT_POLLS: u32 = 100_000;
+
+// I2C0 register bits
+const CTL0_I2CEN: u32 = 1 << 0;
+const CTL0_START: u32 = 1 << 8;
+const CTL0_STOP: u32 = 1 << 9;
+const STAT0_SBSEND: u32 = 1 << 0;
+const STAT0_ADDSEND: u32 = 1 << 1;
+const STAT0_BTC: u32 = 1 << 2;
+const STAT0_TBE: u32 = 1 << 7;
+const STAT0_AERR: u32 = 1 << 10;
+
+// RCU bits
+const APB2EN_AFEN: u32 = 1 << 0;
+const APB2EN_PBEN: u32 = 1 << 3;
+const APB1EN_I2C0EN: u32 = 1 << 21;
+const APB1RST_I2C0RST: u32 = 1 << 21;
+
+// PB6 and PB7 in GPIOB CTL0: alternate function open drain, 50 MHz
+const CTL0_PB6_PB7: u32 = 0xFF << 24;
+const CTL0_PB6_PB7_AF_OD: u32 = 0xFF << 24;
+
+/// A bare I2C0 master on PB6 and PB7 for the panic handler. It sets the
+/// peripheral up from its registers, so it needs neither main's bus nor
+/// its RCU, and leaves every other pin of the port as it is.
+pub struct CrashI2c {
+    i2c: pac::I2C0,
+}
+
+impl CrashI2c {
+    /// Resets I2C0, whatever main left it doing, and starts it over on the
+    /// APB1 clock main set up.
+    pub fn new(i2c: pac::I2C0, rcu: &pac::RCU, gpiob: &pac::GPIOB) -> Self {
+        let hz = APB1_HZ.load(Ordering::Relaxed);
+        let mhz = hz / 1_000_000;
+        unsafe {
+            rcu.apb2en
+                .modify(|r, w| w.bits(r.bits() | APB2EN_AFEN | APB2EN_PBEN));
+            rcu.apb1en.modify(|r, w| w.bits(r.bits() | APB1EN_I2C0EN));
+            rcu.apb1rst
+                .modify(|r, w| w.bits(r.bits() | APB1RST_I2C0RST));
+            rcu.apb1rst
+                .modify(|r, w| w.bits(r.bits() & !APB1RST_I2C0RST));
+
+            gpiob
+                .ctl0
+                .modify(|r, w| w.bits((r.bits() & !CTL0_PB6_PB7) | CTL0_PB6_PB7_AF_OD));
+
+            i2c.ctl1.write(|w| w.bits(mhz));
+            // high and low half of the bus clock, 4 is the least it takes
+            i2c.ckcfg.write(|w| w.bits((hz / (2 * BUS_HZ)).max(4)));
+            // 1000 ns rise time
+            i2c.rt.write(|w| w.bits(mhz + 1));
+            i2c.ctl0.write(|w| w.bits(CTL0_I2CEN));
+        }
+        Self { i2c }
+    }
+
+    // Polls until `flag` is set, or the address went unanswered.
+    fn wait_for(&self, flag: u32) -> Result<(), ()> {
+        for _ in 0..TIMEOUT_POLLS {
+            let stat = self.i2c.stat0.read().bits();
+            if stat & STAT0_AERR != 0 {
+                return Err(());
+            }
+            if stat & flag != 0 {
+                return Ok(());
+            }
+        }
+        Err(())
+    }
+
+    fn send(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        self.i2c
+            .ctl0
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL0_START) });
+        self.wait_for(STAT0_SBSEND)?;
+        self.i2c
+            .data
+            .write(|w| unsafe { w.bits((address as u32) << 1) });
+        self.wait_for(STAT0_ADDSEND)?;
+        // reading STAT1 after STAT0 clears ADDSEND
+        self.i2c.stat1.read();
+
+        for &byte in bytes {
+            self.wait_for(STAT0_TBE)?;
+            self.i2c.data.write(|w| unsafe { w.bits(byte as u32) });
+        }
+        self.wait_for(STAT0_BTC)
+    }
+}
+
+impl Write for CrashI2c {
+    type Error = ();
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+        let result = self.send(address, bytes);
+        self.i2c
+            .ctl0
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL0_STOP) });
+        // a NACK stays flagged until cleared, the next transfer would see it
+        self.i2c
+            .stat0
+            .modify(|r, w| unsafe { w.bits(r.bits() & !STAT0_AERR) });
+        result
+    }
+}
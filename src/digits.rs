@@ -0,0 +1,201 @@
+//! A 3x5 pixel font for numbers and the formatting that keeps them short
+//! enough for the 16 pixels across the screen.
+//!
+//! Glyphs have a pixel between them. The decimal point goes into that
+//! gap a row below the digits, so "12.3k" is as wide as "1234": 15 pixels.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+/// Pixels of a digit along the text, the decimal point hangs one below.
+pub const GLYPH_HEIGHT: i32 = 5;
+
+/// Enough for anything `compact` and `clock` write.
+pub const BUF_LEN: usize = 8;
+
+// Rows top to bottom, the leftmost pixel in the highest of the glyph's
+// bits. The last row is below the line.
+struct Glyph {
+    width: i32,
+    rows: [u8; 6],
+}
+
+const fn wide(rows: [u8; 5]) -> Glyph {
+    Glyph {
+        width: 3,
+        rows: [rows[0], rows[1], rows[2], rows[3], rows[4], 0],
+    }
+}
+
+const DIGITS: [Glyph; 10] = [
+    wide([0b111, 0b101, 0b101, 0b101, 0b111]),
+    wide([0b010, 0b110, 0b010, 0b010, 0b111]),
+    wide([0b111, 0b001, 0b111, 0b100, 0b111]),
+    wide([0b111, 0b001, 0b111, 0b001, 0b111]),
+    wide([0b101, 0b101, 0b111, 0b001, 0b001]),
+    wide([0b111, 0b100, 0b111, 0b001, 0b111]),
+    wide([0b111, 0b100, 0b111, 0b101, 0b111]),
+    wide([0b111, 0b001, 0b001, 0b001, 0b001]),
+    wide([0b111, 0b101, 0b111, 0b101, 0b111]),
+    wide([0b111, 0b101, 0b111, 0b001, 0b111]),
+];
+
+const KILO: Glyph = wide([0b100, 0b101, 0b110, 0b101, 0b101]);
+const MEGA: Glyph = wide([0b101, 0b111, 0b111, 0b101, 0b101]);
+const GIGA: Glyph = wide([0b111, 0b100, 0b101, 0b101, 0b111]);
+const MINUTES: Glyph = wide([0b000, 0b110, 0b111, 0b101, 0b101]);
+const HOURS: Glyph = wide([0b100, 0b100, 0b111, 0b101, 0b101]);
+const COLON: Glyph = Glyph {
+    width: 1,
+    rows: [0, 1, 0, 1, 0, 0],
+};
+const POINT: Glyph = Glyph {
+    width: 1,
+    rows: [0, 0, 0, 0, 0, 1],
+};
+// anything the font doesn't have
+const BLANK: Glyph = wide([0; 5]);
+
+fn glyph(c: u8) -> &'static Glyph {
+    match c {
+        b'0'..=b'9' => &DIGITS[(c - b'0') as usize],
+        b'k' => &KILO,
+        b'M' => &MEGA,
+        b'G' => &GIGA,
+        b'm' => &MINUTES,
+        b'h' => &HOURS,
+        b':' => &COLON,
+        b'.' => &POINT,
+        _ => &BLANK,
+    }
+}
+
+// Left edge of each glyph of `text`, from 0, and the total width.
+fn advance(text: &str, mut visit: impl FnMut(i32, &Glyph)) -> i32 {
+    let mut x = 0;
+    let mut last = None;
+    for c in text.bytes() {
+        // the point is the gap
+        if last.is_some() && last != Some(b'.') && c != b'.' {
+            x += 1;
+        }
+        let glyph = glyph(c);
+        visit(x, glyph);
+        x += glyph.width;
+        last = Some(c);
+    }
+    x
+}
+
+/// Pixels `text` takes along a row.
+pub fn width(text: &str) -> i32 {
+    advance(text, |_, _| {})
+}
+
+/// Draws `text` centered on `x` with its bottom row on `y`.
+pub fn draw<D>(text: &str, (x, y): (i32, i32), disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let left = x - width(text) / 2;
+    let top = y - GLYPH_HEIGHT + 1;
+    let mut result = Ok(());
+    advance(text, |gx, glyph| {
+        if result.is_err() {
+            return;
+        }
+        let pixels = (0..GLYPH_HEIGHT + 1).flat_map(|row| {
+            (0..glyph.width)
+                .filter(move |&col| glyph.rows[row as usize] & (1 << (glyph.width - 1 - col)) != 0)
+                .map(move |col| Pixel(Point::new(left + gx + col, top + row), BinaryColor::On))
+        });
+        result = disp.draw_iter(pixels);
+    });
+    result
+}
+
+/// Breaks `text` in two for stacking when a row is too narrow: a suffix
+/// letter goes on its own, anything else before the last three digits,
+/// like a thousands separator.
+pub fn split(text: &str) -> (&str, &str) {
+    let bytes = text.as_bytes();
+    let at = if matches!(bytes.last(), Some(c) if c.is_ascii_alphabetic()) {
+        bytes.len() - 1
+    } else {
+        bytes.len().saturating_sub(3)
+    };
+    text.split_at(at)
+}
+
+// Writes the decimal digits of `n` with a point before the last `point`
+// of them, returns how many bytes it took.
+fn write_digits(mut n: u32, point: usize, buf: &mut [u8]) -> usize {
+    let mut digits = [0u8; 10];
+    let mut count = 0;
+    loop {
+        digits[count] = b'0' + (n % 10) as u8;
+        count += 1;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+
+    let mut len = 0;
+    for i in (0..count).rev() {
+        buf[len] = digits[i];
+        len += 1;
+        if i == point && point > 0 {
+            buf[len] = b'.';
+            len += 1;
+        }
+    }
+    len
+}
+
+/// Writes `value` with at most four digits: 12345 as "12.3k", 123456 as
+/// "123k" and 1234567 as "1.23M". It's cut rather than rounded, so it
+/// never shows more than there is.
+pub fn compact(value: u32, buf: &mut [u8; BUF_LEN]) -> &str {
+    let (unit, suffix) = match value {
+        0..=9_999 => (1, None),
+        10_000..=999_999 => (1_000, Some(b'k')),
+        1_000_000..=999_999_999 => (1_000_000, Some(b'M')),
+        _ => (1_000_000_000, Some(b'G')),
+    };
+
+    // three significant digits, however many are left of the point
+    let point = match (suffix, value / unit) {
+        (Some(_), 0..=9) => 2,
+        (Some(_), 10..=99) => 1,
+        _ => 0,
+    };
+    let mut len = write_digits(value / (unit / 10u32.pow(point as u32)), point, buf);
+    if let Some(suffix) = suffix {
+        buf[len] = suffix;
+        len += 1;
+    }
+    core::str::from_utf8(&buf[..len]).unwrap_or("")
+}
+
+/// Writes a duration in `seconds` as "m:ss", from 10 minutes on as whole
+/// minutes, "12m", and from 1000 on as whole hours up to "999h".
+pub fn clock(seconds: u32, buf: &mut [u8; BUF_LEN]) -> &str {
+    let minutes = seconds / 60;
+    let len = if minutes < 10 {
+        let len = write_digits(minutes, 0, buf);
+        let seconds = (seconds % 60) as u8;
+        buf[len] = b':';
+        buf[len + 1] = b'0' + seconds / 10;
+        buf[len + 2] = b'0' + seconds % 10;
+        len + 3
+    } else if minutes < 1000 {
+        let len = write_digits(minutes, 0, buf);
+        buf[len] = b'm';
+        len + 1
+    } else {
+        let len = write_digits((minutes / 60).min(999), 0, buf);
+        buf[len] = b'h';
+        len + 1
+    };
+    core::str::from_utf8(&buf[..len]).unwrap_or("")
+}
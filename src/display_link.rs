@@ -0,0 +1,245 @@
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::blocking::i2c::Write;
+use gd32vf103_pac as pac;
+use pinecil_tetris::double_buffer::DoubleBuffer;
+use pinecil_tetris::frame::{Area, Framebuffer, FRAME_LEN};
+
+const ADDRESS: u8 = 0x3C;
+
+// SSD1306 control bytes: one command byte follows, or data up to the STOP
+const CONTROL_COMMAND: u8 = 0x80;
+const CONTROL_DATA: u8 = 0x40;
+const SET_COLUMN_ADDRESS: u8 = 0x21;
+const SET_PAGE_ADDRESS: u8 = 0x22;
+
+// The column and page address commands with their arguments, each byte
+// behind a command control byte, then the data control byte.
+const AREA_HEADER_LEN: usize = 13;
+
+/// A full frame plus the commands that say where it goes.
+pub const TRANSFER_LEN: usize = AREA_HEADER_LEN + FRAME_LEN;
+
+// I2C0 register bits
+const CTL0_START: u32 = 1 << 8;
+const CTL0_STOP: u32 = 1 << 9;
+const CTL1_DMAON: u32 = 1 << 11;
+const STAT0_SBSEND: u32 = 1 << 0;
+const STAT0_ADDSEND: u32 = 1 << 1;
+const STAT0_BTC: u32 = 1 << 2;
+const STAT0_AERR: u32 = 1 << 10;
+const STAT1_I2CBSY: u32 = 1 << 1;
+
+// Status polls before a transfer counts as stuck, a full frame at 400 kHz
+// takes well under a tenth of that.
+const SPIN_LIMIT: u32 = 1_000_000;
+
+// DMA0 channel 5 is wired to I2C0 TX, its flags sit in bits 20..24
+const DMA_CH5_FLAGS: u32 = 0xF << 20;
+const DMA_CH5_FTF: u32 = 1 << 21;
+const DMA_CTL_CHEN: u32 = 1 << 0;
+const DMA_CTL_DIR: u32 = 1 << 4;
+const DMA_CTL_MNAGA: u32 = 1 << 7;
+
+/// SSD1306 interface that sends commands blocking and pixel data by DMA.
+///
+/// An area goes out in one transfer together with its address commands, so
+/// the next one can be queued into the back buffer while it is sent. Commands
+/// sent in the meantime wait for the transfer to finish first.
+pub struct DisplayLink<I2C> {
+    i2c: I2C,
+    dma: pac::DMA0,
+    buffers: &'static mut DoubleBuffer<TRANSFER_LEN>,
+}
+
+impl<I2C: Write> DisplayLink<I2C> {
+    /// `i2c` has to be the I2C0 driver, the DMA path drives its registers directly.
+    pub fn new(i2c: I2C, dma: pac::DMA0, buffers: &'static mut DoubleBuffer<TRANSFER_LEN>) -> Self {
+        unsafe { (*pac::RCU::ptr()).ahben.modify(|_, w| w.dma0en().set_bit()) };
+        Self { i2c, dma, buffers }
+    }
+
+    /// Stops any transfer and hands back what `new` took.
+    pub fn release(mut self) -> (I2C, pac::DMA0, &'static mut DoubleBuffer<TRANSFER_LEN>) {
+        if self.buffers.is_busy() {
+            self.stop();
+        }
+        (self.i2c, self.dma, self.buffers)
+    }
+
+    /// Blocks until the frame in flight, if any, has been sent.
+    pub fn wait(&mut self) -> Result<(), DisplayError> {
+        if !self.buffers.is_busy() {
+            return Ok(());
+        }
+        let i2c = unsafe { &*pac::I2C0::ptr() };
+
+        let result = spin_until(|| {
+            if i2c.stat0.read().bits() & STAT0_AERR != 0 {
+                return Err(DisplayError::BusWriteError);
+            }
+            Ok(self.dma.intf.read().bits() & DMA_CH5_FTF != 0)
+        })
+        // the DMA is done once the last byte is in the data register,
+        // the STOP has to wait for it to leave
+        .and_then(|_| spin_until(|| Ok(i2c.stat0.read().bits() & STAT0_BTC != 0)));
+
+        self.stop();
+        result
+    }
+
+    /// Whether the frame in flight, if any, has left, without blocking.
+    pub fn is_idle(&self) -> bool {
+        let i2c = unsafe { &*pac::I2C0::ptr() };
+        !self.buffers.is_busy()
+            || (self.dma.intf.read().bits() & DMA_CH5_FTF != 0
+                && i2c.stat0.read().bits() & STAT0_BTC != 0)
+    }
+
+    /// Fills the back buffer with `area` of `frame`, to be shown from `column`
+    /// and `page` of the panel. Doesn't wait for the frame in flight, returns
+    /// the length to pass to `start_transfer` once that one is done.
+    pub fn queue_area(&mut self, frame: &Framebuffer, area: &Area, column: u8, page: u8) -> usize {
+        let back = self.buffers.back_mut();
+        back[..AREA_HEADER_LEN].copy_from_slice(&[
+            CONTROL_COMMAND,
+            SET_COLUMN_ADDRESS,
+            CONTROL_COMMAND,
+            column,
+            CONTROL_COMMAND,
+            column + area.width - 1,
+            CONTROL_COMMAND,
+            SET_PAGE_ADDRESS,
+            CONTROL_COMMAND,
+            page,
+            CONTROL_COMMAND,
+            page + area.pages - 1,
+            CONTROL_DATA,
+        ]);
+        AREA_HEADER_LEN + frame.copy_area(area, &mut back[AREA_HEADER_LEN..])
+    }
+
+    /// The data of the last queued area that was started, without its
+    /// commands. It is on the panel once `wait` says so.
+    pub fn sent_area(&self) -> &[u8] {
+        self.buffers.front().get(AREA_HEADER_LEN..).unwrap_or(&[])
+    }
+
+    fn stop(&mut self) {
+        let i2c = unsafe { &*pac::I2C0::ptr() };
+
+        i2c.ctl0
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL0_STOP) });
+        i2c.ctl1
+            .modify(|r, w| unsafe { w.bits(r.bits() & !CTL1_DMAON) });
+        self.dma.ch5ctl.write(|w| unsafe { w.bits(0) });
+        self.dma.intc.write(|w| unsafe { w.bits(DMA_CH5_FLAGS) });
+        self.buffers.finish();
+    }
+
+    /// Sends the first `len` bytes of the back buffer, fails while the
+    /// previous transfer hasn't been waited for.
+    pub fn start_transfer(&mut self, len: usize) -> Result<(), DisplayError> {
+        let i2c = unsafe { &*pac::I2C0::ptr() };
+        spin_until(|| Ok(i2c.stat1.read().bits() & STAT1_I2CBSY == 0))?;
+        let front = self.buffers.start(len).ok_or(DisplayError::BusWriteError)?;
+
+        self.dma.ch5ctl.write(|w| unsafe { w.bits(0) });
+        self.dma.intc.write(|w| unsafe { w.bits(DMA_CH5_FLAGS) });
+        self.dma
+            .ch5paddr
+            .write(|w| unsafe { w.bits(&i2c.data as *const _ as u32) });
+        self.dma
+            .ch5maddr
+            .write(|w| unsafe { w.bits(front.as_ptr() as u32) });
+        self.dma
+            .ch5cnt
+            .write(|w| unsafe { w.bits(front.len() as u32) });
+
+        i2c.ctl1
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL1_DMAON) });
+        i2c.ctl0
+            .modify(|r, w| unsafe { w.bits(r.bits() | CTL0_START) });
+        let addressed =
+            spin_until(|| Ok(i2c.stat0.read().bits() & STAT0_SBSEND != 0)).and_then(|_| {
+                i2c.data.write(|w| unsafe { w.bits((ADDRESS << 1) as u32) });
+                spin_until(|| {
+                    let stat0 = i2c.stat0.read().bits();
+                    if stat0 & STAT0_AERR != 0 {
+                        return Err(DisplayError::BusWriteError);
+                    }
+                    Ok(stat0 & STAT0_ADDSEND != 0)
+                })
+            });
+        if let Err(e) = addressed {
+            self.stop();
+            return Err(e);
+        }
+        // reading STAT0 then STAT1 clears ADDSEND
+        let _ = i2c.stat0.read();
+        let _ = i2c.stat1.read();
+
+        // the channel takes over from here, feeding the data register on TBE
+        self.dma
+            .ch5ctl
+            .write(|w| unsafe { w.bits(DMA_CTL_CHEN | DMA_CTL_DIR | DMA_CTL_MNAGA) });
+        Ok(())
+    }
+}
+
+// Polls `done` until it says so, gives up after `SPIN_LIMIT` tries.
+fn spin_until<F>(mut done: F) -> Result<(), DisplayError>
+where
+    F: FnMut() -> Result<bool, DisplayError>,
+{
+    for _ in 0..SPIN_LIMIT {
+        if done()? {
+            return Ok(());
+        }
+    }
+    Err(DisplayError::BusWriteError)
+}
+
+impl<I2C: Write> WriteOnlyDataCommand for DisplayLink<I2C> {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.wait()?;
+
+        // commands are a handful of bytes, not worth a DMA transfer
+        let mut buf = [0u8; 16];
+        let len = match cmd {
+            DataFormat::U8(bytes) if bytes.len() < buf.len() => {
+                buf[1..=bytes.len()].copy_from_slice(bytes);
+                bytes.len() + 1
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+
+        self.i2c
+            .write(ADDRESS, &buf[..len])
+            .map_err(|_| DisplayError::BusWriteError)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        // fill the back buffer while the previous frame may still be going out
+        let back = self.buffers.back_mut();
+        back[0] = CONTROL_DATA;
+        let len = match buf {
+            DataFormat::U8(bytes) => {
+                let count = bytes.len().min(TRANSFER_LEN - 1);
+                back[1..=count].copy_from_slice(&bytes[..count]);
+                count + 1
+            }
+            DataFormat::U8Iter(iter) => {
+                let mut len = 1;
+                for (slot, byte) in back[1..].iter_mut().zip(iter) {
+                    *slot = byte;
+                    len += 1;
+                }
+                len
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        };
+
+        self.wait()?;
+        self.start_transfer(len)
+    }
+}
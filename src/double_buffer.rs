@@ -0,0 +1,57 @@
+/// Two transfer buffers, one being sent while the other one is filled.
+///
+/// Knows nothing about the hardware: the driver starts a transfer from
+/// the slice `start` returns and calls `finish` once it is done.
+pub struct DoubleBuffer<const N: usize> {
+    buffers: [[u8; N]; 2],
+    lens: [usize; 2],
+    front: usize,
+    busy: bool,
+}
+
+impl<const N: usize> DoubleBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffers: [[0; N]; 2],
+            lens: [0; 2],
+            front: 1,
+            busy: false,
+        }
+    }
+
+    /// The buffer to fill next, never the one that is being sent.
+    pub fn back_mut(&mut self) -> &mut [u8; N] {
+        &mut self.buffers[1 - self.front]
+    }
+
+    /// Swaps the first `len` bytes of the back buffer to the front and returns
+    /// them for sending, `None` while the previous transfer is unfinished.
+    pub fn start(&mut self, len: usize) -> Option<&[u8]> {
+        if self.busy {
+            return None;
+        }
+        self.front = 1 - self.front;
+        self.lens[self.front] = len.min(N);
+        self.busy = true;
+        Some(&self.buffers[self.front][..self.lens[self.front]])
+    }
+
+    pub fn finish(&mut self) {
+        self.busy = false;
+    }
+
+    pub const fn is_busy(&self) -> bool {
+        self.busy
+    }
+
+    /// What is being, or was last, sent.
+    pub fn front(&self) -> &[u8] {
+        &self.buffers[self.front][..self.lens[self.front]]
+    }
+}
+
+impl<const N: usize> Default for DoubleBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
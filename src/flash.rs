@@ -0,0 +1,102 @@
+use core::ptr;
+
+use gd32vf103_pac as pac;
+use pinecil_tetris::app::SAVE_LEN;
+use pinecil_tetris::settings::{Settings, RECORD_LEN};
+
+// The last two 1K pages of the 128K flash, kept out of FLASH in memory.x.
+const GAME_ADDR: u32 = 0x0801_F800;
+const SETTINGS_ADDR: u32 = 0x0801_FC00;
+
+const FMC_KEY1: u32 = 0x4567_0123;
+const FMC_KEY2: u32 = 0xCDEF_89AB;
+
+/// Settings and the saved game in the reserved flash pages.
+pub struct Storage {
+    fmc: pac::FMC,
+}
+
+impl Storage {
+    pub fn new(fmc: pac::FMC) -> Self {
+        Self { fmc }
+    }
+
+    /// Stored settings, or the defaults if the page is blank or outdated.
+    pub fn load_settings(&self) -> Settings {
+        let mut record = [0u8; RECORD_LEN];
+        read(SETTINGS_ADDR, &mut record);
+        Settings::from_bytes(&record).unwrap_or_default()
+    }
+
+    pub fn save_settings(&mut self, settings: &Settings) {
+        if self.load_settings() == *settings {
+            // spare the flash an erase cycle
+            return;
+        }
+        self.write_page(SETTINGS_ADDR, &settings.to_bytes());
+    }
+
+    /// The saved game record, checked by `App::resume`.
+    pub fn load_game(&self) -> [u8; SAVE_LEN] {
+        let mut record = [0u8; SAVE_LEN];
+        read(GAME_ADDR, &mut record);
+        record
+    }
+
+    pub fn save_game(&mut self, record: &[u8; SAVE_LEN]) {
+        if self.load_game() == *record {
+            return;
+        }
+        self.write_page(GAME_ADDR, record);
+    }
+
+    /// Erases the saved game once it has been picked up.
+    pub fn clear_game(&mut self) {
+        if self.load_game().iter().all(|&byte| byte == 0xFF) {
+            return;
+        }
+        self.write_page(GAME_ADDR, &[]);
+    }
+
+    // `record` has to be of even length, an empty one just erases the page.
+    fn write_page(&mut self, addr: u32, record: &[u8]) {
+        self.unlock();
+
+        // erase the page
+        self.fmc.ctl0.modify(|_, w| w.per().set_bit());
+        self.fmc.addr0.write(|w| unsafe { w.addr().bits(addr) });
+        self.fmc.ctl0.modify(|_, w| w.start().set_bit());
+        self.wait();
+        self.fmc.ctl0.modify(|_, w| w.per().clear_bit());
+
+        // program it a halfword at a time
+        self.fmc.ctl0.modify(|_, w| w.pg().set_bit());
+        for (i, halfword) in record.chunks(2).enumerate() {
+            let value = u16::from_le_bytes([halfword[0], halfword[1]]);
+            unsafe {
+                ptr::write_volatile((addr as usize + i * 2) as *mut u16, value);
+            }
+            self.wait();
+        }
+        self.fmc.ctl0.modify(|_, w| w.pg().clear_bit());
+
+        self.fmc.ctl0.modify(|_, w| w.lk().set_bit());
+    }
+
+    fn unlock(&mut self) {
+        if self.fmc.ctl0.read().lk().bit_is_set() {
+            self.fmc.key0.write(|w| unsafe { w.key().bits(FMC_KEY1) });
+            self.fmc.key0.write(|w| unsafe { w.key().bits(FMC_KEY2) });
+        }
+    }
+
+    fn wait(&self) {
+        while self.fmc.stat0.read().busy().bit_is_set() {}
+    }
+}
+
+fn read(addr: u32, out: &mut [u8]) {
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = unsafe { ptr::read_volatile((addr as usize + i) as *const u8) };
+    }
+}
@@ -0,0 +1,181 @@
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+/// Panel width in pixels, in its native orientation.
+pub const WIDTH: usize = 96;
+/// Panel height in pixels, in its native orientation.
+pub const HEIGHT: usize = 16;
+/// SSD1306 pages, each one a row of 8 pixel tall columns.
+pub const PAGES: usize = HEIGHT / 8;
+
+pub const FRAME_LEN: usize = WIDTH * PAGES;
+
+/// A rectangle of display memory, in columns and pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Area {
+    pub column: u8,
+    pub width: u8,
+    pub page: u8,
+    pub pages: u8,
+}
+
+impl Area {
+    pub const fn byte_count(&self) -> usize {
+        self.width as usize * self.pages as usize
+    }
+
+    /// The smallest area holding both.
+    pub fn union(&self, other: &Area) -> Area {
+        let column = self.column.min(other.column);
+        let page = self.page.min(other.page);
+        Area {
+            column,
+            width: (self.column + self.width).max(other.column + other.width) - column,
+            page,
+            pages: (self.page + self.pages).max(other.page + other.pages) - page,
+        }
+    }
+}
+
+/// Frame buffer in the SSD1306 memory layout that remembers what the panel
+/// currently shows, so only the changed bytes have to go over I2C.
+pub struct Framebuffer {
+    buffer: [u8; FRAME_LEN],
+    shown: [u8; FRAME_LEN],
+    /// The panel content is unknown (after init or a rotation change).
+    stale: bool,
+    /// Draw with x and y swapped, for the portrait orientations.
+    transposed: bool,
+}
+
+impl Framebuffer {
+    pub const fn new(transposed: bool) -> Self {
+        Self {
+            buffer: [0; FRAME_LEN],
+            shown: [0; FRAME_LEN],
+            stale: true,
+            transposed,
+        }
+    }
+
+    /// Changing the orientation redraws the whole panel on the next flush.
+    pub fn set_transposed(&mut self, transposed: bool) {
+        self.transposed = transposed;
+        self.stale = true;
+    }
+
+    /// Forces the next flush to send the whole frame.
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    pub fn clear_buffer(&mut self) {
+        self.buffer = [0; FRAME_LEN];
+    }
+
+    pub fn as_bytes(&self) -> &[u8; FRAME_LEN] {
+        &self.buffer
+    }
+
+    /// The smallest area holding every byte that differs from what the
+    /// panel is known to show, so a frame goes out in a single transfer.
+    pub fn dirty_area(&self) -> Option<Area> {
+        if self.stale {
+            return Some(Area {
+                column: 0,
+                width: WIDTH as u8,
+                page: 0,
+                pages: PAGES as u8,
+            });
+        }
+
+        let mut area: Option<(usize, usize, usize, usize)> = None;
+        for page in 0..PAGES {
+            let start = page * WIDTH;
+            let now = &self.buffer[start..start + WIDTH];
+            let before = &self.shown[start..start + WIDTH];
+
+            let first = now.iter().zip(before).position(|(a, b)| a != b);
+            let last = now.iter().zip(before).rposition(|(a, b)| a != b);
+            if let (Some(first), Some(last)) = (first, last) {
+                area = Some(match area {
+                    Some((x0, x1, p0, _)) => (x0.min(first), x1.max(last), p0, page),
+                    None => (first, last, page, page),
+                });
+            }
+        }
+
+        area.map(|(x0, x1, p0, p1)| Area {
+            column: x0 as u8,
+            width: (x1 - x0 + 1) as u8,
+            page: p0 as u8,
+            pages: (p1 - p0 + 1) as u8,
+        })
+    }
+
+    /// Copies `area` out of the buffer in the order the display expects it
+    /// with horizontal addressing, returns the number of bytes written.
+    pub fn copy_area(&self, area: &Area, out: &mut [u8]) -> usize {
+        let width = area.width as usize;
+        for (i, page) in (area.page as usize..(area.page + area.pages) as usize).enumerate() {
+            let start = page * WIDTH + area.column as usize;
+            out[i * width..(i + 1) * width].copy_from_slice(&self.buffer[start..start + width]);
+        }
+        area.byte_count()
+    }
+
+    /// Takes note that the panel shows `bytes` in `area`, laid out as
+    /// `copy_area` wrote them. Call once they have been sent; the buffer
+    /// may have moved on by then.
+    pub fn commit_area(&mut self, area: &Area, bytes: &[u8]) {
+        let width = area.width as usize;
+        for (i, page) in (area.page as usize..(area.page + area.pages) as usize).enumerate() {
+            let start = page * WIDTH + area.column as usize;
+            self.shown[start..start + width].copy_from_slice(&bytes[i * width..(i + 1) * width]);
+        }
+        if area.byte_count() == FRAME_LEN {
+            self.stale = false;
+        }
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, on: bool) {
+        let (x, y) = if self.transposed { (y, x) } else { (x, y) };
+        if !(0..WIDTH as i32).contains(&x) || !(0..HEIGHT as i32).contains(&y) {
+            return;
+        }
+
+        let index = (y as usize / 8) * WIDTH + x as usize;
+        let mask = 1 << (y % 8);
+        if on {
+            self.buffer[index] |= mask;
+        } else {
+            self.buffer[index] &= !mask;
+        }
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        if self.transposed {
+            Size::new(HEIGHT as u32, WIDTH as u32)
+        } else {
+            Size::new(WIDTH as u32, HEIGHT as u32)
+        }
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point.x, point.y, color.is_on());
+        }
+        Ok(())
+    }
+}
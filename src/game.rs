@@ -1,317 +1,528 @@
-use bitvec::{bitarr, order::Lsb0, BitArr};
 use oorandom::Rand32;
 
-enum MoveDirection {
+/// Rate at which `Tetris::run` is called, once per frame.
+pub const TICK_HZ: u32 = 60;
+
+/// One cell per frame in the fixed point unit gravity is given in, so
+/// 1/256 of a cell is the smallest step.
+pub const G: u16 = 256;
+
+// Fall speed for each level in 1/256 cells per frame, from 4 up to 16
+// cells a second.
+const GRAVITY: [u16; 10] = [17, 20, 23, 26, 31, 36, 42, 49, 58, 68];
+
+// Lines per level.
+const LEVEL_LINES: u32 = 10;
+
+/// How the next piece is picked.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Randomizer {
+    /// Any of the seven, every time.
+    Uniform,
+    /// All seven in a shuffled bag before any repeats.
+    Bag,
+}
+
+#[derive(Clone, Copy)]
+pub struct Rules {
+    pub start_level: u8,
+    /// Filled cells needed for a row to clear.
+    pub clear_threshold: usize,
+    /// Frames between a piece locking and the next one spawning.
+    pub spawn_delay: u8,
+    /// Frames a piece rests on the stack before it locks.
+    pub lock_delay: u8,
+    /// Frames added to the spawn delay when a lock clears lines.
+    pub clear_delay: u8,
+    pub randomizer: Randomizer,
+    /// Columns of the well, `MIN_WIDTH` to `MAX_WIDTH`.
+    pub width: u8,
+    /// Rows of the well, `MIN_HEIGHT` to `MAX_HEIGHT`.
+    pub height: u8,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            start_level: 0,
+            // let's allow a missing pixel/block since
+            // the screen is quite small and its borders
+            // are hard to make out..
+            clear_threshold: 7,
+            spawn_delay: 5 * (TICK_HZ / 4) as u8,
+            lock_delay: (TICK_HZ / 4) as u8,
+            clear_delay: (TICK_HZ / 4) as u8,
+            randomizer: Randomizer::Uniform,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+        }
+    }
+}
+
+impl Rules {
+    /// A grid row with every column of the well filled.
+    pub const fn full_row(&self) -> u16 {
+        u16::MAX >> (MAX_WIDTH - self.width as usize)
+    }
+}
+
+// Limits of the well: one bit per column in a `u16` grid row, and one bit
+// per row in the `u32` of cleared rows in `Events`.
+pub const MAX_WIDTH: usize = 16;
+pub const MAX_HEIGHT: usize = 32;
+// the widest piece has to fit, and the spawn rows with room below
+pub const MIN_WIDTH: usize = 4;
+pub const MIN_HEIGHT: usize = 8;
+
+pub const DEFAULT_WIDTH: u8 = 8;
+pub const DEFAULT_HEIGHT: u8 = 32;
+
+// One bit per `Piece::ALL` entry.
+const FULL_BAG: u8 = (1 << 7) - 1;
+
+/// Size of a serialized game, see `Tetris::to_bytes`.
+pub const STATE_LEN: usize = 108;
+
+/// Which way the bounce mover steps the piece.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveDirection {
     Left,
     Right,
 }
 
-#[derive(Clone)]
-pub struct Block {
-    pub shape: [BitArr!(for 4); 4],
-    pub size: u8,
-    pub pos: (i32, i32),
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Piece {
+    O,
+    L,
+    J,
+    Z,
+    S,
+    T,
+    I,
 }
 
-impl Block {
-    pub fn move_left(&mut self) {
-        self.pos.0 -= 1;
+impl Piece {
+    pub const ALL: [Piece; 7] = [
+        Piece::O,
+        Piece::L,
+        Piece::J,
+        Piece::Z,
+        Piece::S,
+        Piece::T,
+        Piece::I,
+    ];
+
+    // Rows of the spawn orientation, bit `j` is column `j`,
+    // and the size of the square it rotates in.
+    const fn shape(self) -> ([u8; 4], usize) {
+        match self {
+            Piece::O => ([0b0011, 0b0011, 0, 0], 2),
+            Piece::L => ([0b0100, 0b0111, 0, 0], 3),
+            Piece::J => ([0b0001, 0b0111, 0, 0], 3),
+            Piece::Z => ([0b0011, 0b0110, 0, 0], 3),
+            Piece::S => ([0b0110, 0b0011, 0, 0], 3),
+            Piece::T => ([0b0010, 0b0111, 0, 0], 3),
+            Piece::I => ([0, 0b1111, 0, 0], 4),
+        }
     }
 
-    pub fn move_right(&mut self) {
-        self.pos.0 += 1;
+    const fn index(self) -> usize {
+        self as usize
     }
+}
 
-    pub fn create_square() -> Self {
-        Self {
-            shape: [
-                bitarr![1, 1, 0, 0],
-                bitarr![1, 1, 0, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 2,
-            pos: (8 / 2, 1),
+// Turns a shape a quarter clockwise within its `size` square.
+const fn rotate(rows: [u8; 4], size: usize) -> [u8; 4] {
+    let mut rotated = [0u8; 4];
+    let mut i = 0;
+    while i < size {
+        let mut j = 0;
+        while j < size {
+            if rows[i] & (1 << j) != 0 {
+                rotated[j] |= 1 << (size - 1 - i);
+            }
+            j += 1;
         }
+        i += 1;
     }
+    rotated
+}
 
-    pub fn create_l() -> Self {
-        Self {
-            shape: [
-                bitarr![0, 0, 1, 0],
-                bitarr![1, 1, 1, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 3,
-            pos: (8 / 2, 1),
+const fn rotation_table() -> [[[u8; 4]; 4]; 7] {
+    let mut table = [[[0u8; 4]; 4]; 7];
+    let mut p = 0;
+    while p < 7 {
+        let (mut rows, size) = Piece::ALL[p].shape();
+        let mut r = 0;
+        while r < 4 {
+            table[p][r] = rows;
+            rows = rotate(rows, size);
+            r += 1;
         }
+        p += 1;
     }
+    table
+}
+
+// Every orientation of every piece as row masks, computed at compile time.
+static ROTATIONS: [[[u8; 4]; 4]; 7] = rotation_table();
 
-    pub fn create_j() -> Self {
+#[derive(Clone, Copy, Debug)]
+pub struct Block {
+    pub piece: Piece,
+    /// Quarter turns clockwise from the spawn orientation.
+    pub rotation: u8,
+    pub pos: (i32, i32),
+}
+
+impl Block {
+    /// The piece at its spawn point in a well `width` columns wide.
+    pub const fn new(piece: Piece, width: usize) -> Self {
+        let y = match piece {
+            Piece::I => 2,
+            _ => 1,
+        };
+        // right of the middle, as long as the whole box fits
+        let (_, size) = piece.shape();
+        let x = if width / 2 + size <= width {
+            width / 2
+        } else {
+            width - size
+        };
         Self {
-            shape: [
-                bitarr![1, 0, 0, 0],
-                bitarr![1, 1, 1, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 3,
-            pos: (8 / 2, 1),
+            piece,
+            rotation: 0,
+            pos: (x as i32, y),
         }
     }
 
-    pub fn create_z() -> Self {
-        Self {
-            shape: [
-                bitarr![1, 1, 0, 0],
-                bitarr![0, 1, 1, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 3,
-            pos: (8 / 2, 1),
-        }
+    pub fn move_left(&mut self) {
+        self.pos.0 -= 1;
     }
 
-    pub fn create_s() -> Self {
-        Self {
-            shape: [
-                bitarr![0, 1, 1, 0],
-                bitarr![1, 1, 0, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 3,
-            pos: (8 / 2, 1),
-        }
+    pub fn move_right(&mut self) {
+        self.pos.0 += 1;
     }
 
-    pub fn create_t() -> Self {
-        Self {
-            shape: [
-                bitarr![0, 1, 0, 0],
-                bitarr![1, 1, 1, 0],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 3,
-            pos: (8 / 2, 1),
-        }
+    pub fn rotate(&mut self) {
+        self.rotation = (self.rotation + 1) % 4;
     }
 
-    pub fn create_i() -> Self {
-        Self {
-            shape: [
-                bitarr![0, 0, 0, 0],
-                bitarr![1, 1, 1, 1],
-                bitarr![0, 0, 0, 0],
-                bitarr![0, 0, 0, 0],
-            ],
-            size: 4,
-            pos: (8 / 2, 2),
-        }
+    pub fn rotate_ccw(&mut self) {
+        self.rotation = (self.rotation + 3) % 4;
+    }
+
+    /// The rows of the 4x4 box at `pos`, bit `j` is column `pos.0 + j`.
+    pub fn rows(&self) -> &'static [u8; 4] {
+        &ROTATIONS[self.piece.index()][self.rotation as usize]
+    }
+
+    /// Grid coordinates of the four cells.
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.rows().iter().enumerate().flat_map(move |(i, &row)| {
+            (0..4)
+                .filter(move |j| row & (1 << j) != 0)
+                .map(move |j| (self.pos.0 + j, self.pos.1 + i as i32))
+        })
     }
 }
 
+/// What happened in the game since the events were last taken, for
+/// animations. Only the last lock is kept.
+#[derive(Clone, Copy, Default)]
+pub struct Events {
+    /// The piece that locked into the grid.
+    pub locked: Option<Block>,
+    /// Rows the lock cleared, bit `y` for row `y` of `before_clear`.
+    pub cleared: u32,
+    /// The grid with the locked piece in it, before the rows were removed.
+    pub before_clear: [u16; MAX_HEIGHT],
+    /// The game topped out.
+    pub ended: bool,
+}
+
 pub struct Tetris {
+    rules: Rules,
     current_block: Option<Block>,
     block_cooldown: u8,
-    grid: [BitArr!(for 8); 32],
+    // frames the piece has been resting on the stack
+    lock_timer: u8,
+    // progress towards the next cell of fall, in 1/`G` cells
+    gravity_acc: u16,
+    // rows past the height of the well stay empty
+    grid: [u16; MAX_HEIGHT],
     rng: Rand32,
+    // pieces left in the bag, for `Randomizer::Bag`
+    bag: u8,
     move_direction: MoveDirection,
     score: u32,
+    pieces: u32,
     has_ended: bool,
+    events: Events,
 }
 
 impl Tetris {
     pub fn init() -> Self {
         Self {
+            rules: Rules::default(),
             current_block: None,
             block_cooldown: 0,
-            grid: [bitarr![0; 8]; 32],
+            lock_timer: 0,
+            gravity_acc: 0,
+            grid: [0; MAX_HEIGHT],
             rng: Rand32::new(8),
+            bag: FULL_BAG,
             move_direction: MoveDirection::Left,
             score: 0,
+            pieces: 0,
             has_ended: false,
+            events: Events::default(),
+        }
+    }
+
+    fn next_piece(&mut self) -> Piece {
+        match self.rules.randomizer {
+            Randomizer::Uniform => Piece::ALL[self.rng.rand_range(0..7) as usize],
+            Randomizer::Bag => {
+                if self.bag == 0 {
+                    self.bag = FULL_BAG;
+                }
+                // the nth piece still in the bag
+                let mut n = self.rng.rand_range(0..self.bag.count_ones());
+                let mut i = 0;
+                loop {
+                    if self.bag & (1 << i) != 0 {
+                        if n == 0 {
+                            break;
+                        }
+                        n -= 1;
+                    }
+                    i += 1;
+                }
+                self.bag &= !(1 << i);
+                Piece::ALL[i]
+            }
         }
     }
 
     pub fn add_block(&mut self) {
         if self.current_block.is_none() {
-            let block = match self.rng.rand_range(0..7) {
-                0 => Block::create_square(),
-                1 => Block::create_l(),
-                2 => Block::create_j(),
-                3 => Block::create_z(),
-                4 => Block::create_s(),
-                5 => Block::create_t(),
-                _ => Block::create_i(),
-            };
-            self.current_block = Some(block);
+            let piece = self.next_piece();
+            let block = Block::new(piece, self.rules.width as usize);
+            if self.fits(&block) {
+                self.current_block = Some(block);
+            } else {
+                // no room to spawn
+                self.has_ended = true;
+                self.events.ended = true;
+            }
         }
     }
 
     pub fn rotate_block(&mut self) {
-        if let Some(block) = self.current_block.clone() {
-            let mut rotated_block: Block = block.clone();
-            // the bitarray is rectangular
-            let dim: usize = block.size.into();
-            for i in 0..dim {
-                for j in 0..dim {
-                    rotated_block.shape[j].set(dim - 1 - i, block.shape[i][j]);
-                }
+        if let Some(mut rotated_block) = self.current_block {
+            rotated_block.rotate();
+            if self.fits(&rotated_block) {
+                self.current_block.replace(rotated_block);
             }
-            if self.bounds_check(&rotated_block) {
+        }
+    }
+
+    pub fn rotate_block_ccw(&mut self) {
+        if let Some(mut rotated_block) = self.current_block {
+            rotated_block.rotate_ccw();
+            if self.fits(&rotated_block) {
                 self.current_block.replace(rotated_block);
             }
         }
     }
 
-    fn bounds_check(&mut self, block: &Block) -> bool {
-        let grid_height: i32 = self.grid.len() as i32;
-        for (i, _) in block.shape.iter().enumerate() {
-            for (j, bit) in block.shape[i].iter().enumerate() {
-                let x = block.pos.0 + j as i32;
-                let y = block.pos.1 + i as i32;
+    /// Drops the piece as far as it goes, it locks on the next `run`.
+    pub fn drop_block(&mut self) {
+        if self.current_block.is_none() {
+            return;
+        }
+        while !self.reached_bottom() {
+            self.fall();
+        }
+        self.gravity_acc = 0;
+        self.lock_timer = self.rules.lock_delay;
+    }
 
-                if *bit {
-                    // x or y out of the bounds of the grid
-                    if !(0..=grid_height).contains(&y) || !(0..=7).contains(&x) {
-                        return false;
-                    }
+    /// Whether `block` lies within the grid without overlapping anything.
+    pub fn fits(&self, block: &Block) -> bool {
+        for (i, &row) in block.rows().iter().enumerate() {
+            if row == 0 {
+                continue;
+            }
 
-                    // overlapping with existing grid pixels
-                    if let Some(grid_bit) = self.grid[y as usize].get(x as usize) {
-                        if *grid_bit {
-                            return false;
-                        }
-                    }
-                }
+            let y = block.pos.1 + i as i32;
+            if !(0..self.rules.height as i32).contains(&y) {
+                return false;
+            }
+
+            let x = block.pos.0;
+            let mask = if x >= self.rules.width as i32 {
+                // past the right wall, and too far to shift
+                return false;
+            } else if x >= 0 {
+                (row as u32) << x
+            } else if row.trailing_zeros() as i32 >= -x {
+                (row >> -x) as u32
+            } else {
+                // cut off by the left wall
+                return false;
+            };
+
+            if mask & !(self.rules.full_row() as u32) != 0
+                || mask as u16 & self.grid[y as usize] != 0
+            {
+                return false;
             }
         }
         true
     }
 
-    pub const fn get_grid(&self) -> [BitArr!(for 8); 32] {
-        self.grid
+    /// The grid rows from top to bottom, bit `x` is column `x`. Only the
+    /// first `height` rows are part of the well.
+    pub const fn get_grid(&self) -> &[u16; MAX_HEIGHT] {
+        &self.grid
     }
 
-    pub fn get_block(&self) -> Option<Block> {
-        self.current_block.clone()
+    pub const fn get_block(&self) -> Option<Block> {
+        self.current_block
     }
 
     pub const fn get_score(&self) -> u32 {
         self.score
     }
 
+    pub const fn get_pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    pub fn get_level(&self) -> u8 {
+        let level = self.rules.start_level as u32 + self.score / LEVEL_LINES;
+        level.min(GRAVITY.len() as u32 - 1) as u8
+    }
+
+    pub const fn get_rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub const fn width(&self) -> usize {
+        self.rules.width as usize
+    }
+
+    pub const fn height(&self) -> usize {
+        self.rules.height as usize
+    }
+
+    /// Rules take effect on the next `reset`. A well size or clear
+    /// threshold out of range is brought into it.
+    pub fn set_rules(&mut self, mut rules: Rules) {
+        rules.width = rules.width.clamp(MIN_WIDTH as u8, MAX_WIDTH as u8);
+        rules.height = rules.height.clamp(MIN_HEIGHT as u8, MAX_HEIGHT as u8);
+        rules.clear_threshold = rules.clear_threshold.clamp(1, rules.width as usize);
+        self.rules = rules;
+    }
+
     fn reached_bottom(&self) -> bool {
         if let Some(block) = &self.current_block {
-            for (i, _) in block.shape.iter().enumerate() {
-                for (j, bit) in block.shape[i].iter().enumerate() {
-                    let x = block.pos.0 as usize + j;
-                    let y = block.pos.1 as usize + i;
-                    if y >= (self.grid.len() - 1) {
-                        if *bit {
-                            return true;
-                        }
-                        continue;
-                    } else if let Some(grid_bit) = self.grid[y + 1].get(x) {
-                        if *bit && *grid_bit {
-                            return true;
-                        }
-                    }
-                }
-            }
+            let mut fallen_block = *block;
+            fallen_block.pos.1 += 1;
+            return !self.fits(&fallen_block);
         }
         false
     }
 
     fn fall(&mut self) {
         if let Some(block) = &mut self.current_block {
-            let mut fallen_block: Block = block.clone();
-            fallen_block.pos.1 += 1;
-            self.current_block.replace(fallen_block);
+            block.pos.1 += 1;
         }
     }
 
     fn block_to_grid(&mut self) {
         if let Some(block) = &self.current_block {
-            for (i, _) in block.shape.iter().enumerate() {
-                for (j, bit) in block.shape[i].iter().enumerate() {
-                    if *bit {
-                        let x = j + block.pos.0 as usize;
-                        let y = i + block.pos.1 as usize;
-                        self.grid[y].set(x, true);
-                    }
-                }
+            // only ever called on blocks that fit, so every row is in bounds
+            for (x, y) in block.cells() {
+                self.grid[y as usize] |= 1 << x;
             }
         }
         self.current_block = None;
-        self.block_cooldown = 5;
+        self.block_cooldown = self.rules.spawn_delay;
+        self.lock_timer = 0;
+        self.gravity_acc = 0;
+        self.pieces += 1;
     }
 
     fn clear_line(&mut self, row: usize) {
-        // iterate rows above cleared row
-        for y in (0..row).rev() {
-            for x in 0..self.grid[y].len() {
-                let mut b: Option<bool> = None;
-                if let Some(bit) = self.grid[y].get(x) {
-                    b = Some(*bit);
-                }
-                // copy value to row below
-                if let Some(bb) = b {
-                    self.grid[y + 1].set(x, bb);
-                }
-                self.grid[y].set(x, false);
-            }
+        // move the rows above the cleared row down
+        self.grid.copy_within(0..row, 1);
+        self.grid[0] = 0;
+    }
+
+    // Returns the number of rows cleared.
+    fn check_line_clears(&mut self) -> u32 {
+        let mut cleared = 0;
+        while let Some(row) = self
+            .grid
+            .iter()
+            .position(|row| row.count_ones() as usize >= self.rules.clear_threshold)
+        {
+            self.clear_line(row);
+            self.score += 1;
+            cleared += 1;
         }
+        cleared
     }
 
-    fn check_line_clears(&mut self) {
-        let mut is_clear = true;
-        while is_clear {
-            is_clear = false;
-            let mut clear_row_y = 0;
-            for (y, row) in self.grid.iter().enumerate() {
-                // check if row is clear
-                // let's allow 2 missing pixels/blocks since
-                // the screen is quite small and its borders
-                // are hard to make out..
-                if row.count_ones() >= 7 {
-                    is_clear = true;
-                    clear_row_y = y;
-                    break;
-                }
-            }
-            if is_clear {
-                self.clear_line(clear_row_y);
-                self.score += 1;
+    fn lock(&mut self) {
+        if self.clipping_top() {
+            self.has_ended = true;
+        }
+        let locked = self.current_block;
+        self.block_to_grid();
+
+        // clearing a row never fills another, so these are all that go
+        let before_clear = self.grid;
+        let mut cleared = 0;
+        for (y, row) in before_clear.iter().enumerate() {
+            if row.count_ones() as usize >= self.rules.clear_threshold {
+                cleared |= 1 << y;
             }
         }
+
+        if self.check_line_clears() > 0 {
+            self.block_cooldown = self.block_cooldown.saturating_add(self.rules.clear_delay);
+        }
+        self.events = Events {
+            locked,
+            cleared,
+            before_clear,
+            ended: self.has_ended,
+        };
+    }
+
+    /// The events since the last call, and clears them.
+    pub fn take_events(&mut self) -> Events {
+        core::mem::take(&mut self.events)
     }
 
     fn clipping_top(&self) -> bool {
         if let Some(block) = &self.current_block {
-            for (i, _) in block.shape.iter().enumerate() {
-                for (_, bit) in block.shape[i].iter().enumerate() {
-                    let y = block.pos.1 + i as i32;
-                    if y <= 1 && *bit {
-                        return true;
-                    }
-                }
-            }
+            return block.cells().any(|(_, y)| y <= 1);
         }
         false
     }
 
     pub fn move_block(&mut self) {
-        if let Some(block) = self.current_block.clone() {
+        if let Some(block) = self.current_block {
             let mut moved_block = block;
             match &self.move_direction {
                 MoveDirection::Left => {
                     moved_block.move_left();
-                    if !self.bounds_check(&moved_block) {
+                    if !self.fits(&moved_block) {
                         moved_block.move_right();
                         moved_block.move_right();
                         self.move_direction = MoveDirection::Right;
@@ -319,17 +530,185 @@ impl Tetris {
                 }
                 MoveDirection::Right => {
                     moved_block.move_right();
-                    if !self.bounds_check(&moved_block) {
+                    if !self.fits(&moved_block) {
                         moved_block.move_left();
                         moved_block.move_left();
                         self.move_direction = MoveDirection::Left;
                     }
                 }
             }
-            self.current_block.replace(moved_block);
+            // boxed in on both sides
+            if self.fits(&moved_block) {
+                self.current_block.replace(moved_block);
+            }
         }
     }
 
+    /// Where the next `move_block` goes, after turning around at a wall.
+    pub fn next_move_direction(&self) -> MoveDirection {
+        if let Some(mut moved_block) = self.current_block {
+            match self.move_direction {
+                MoveDirection::Left => moved_block.move_left(),
+                MoveDirection::Right => moved_block.move_right(),
+            }
+            if !self.fits(&moved_block) {
+                return match self.move_direction {
+                    MoveDirection::Left => MoveDirection::Right,
+                    MoveDirection::Right => MoveDirection::Left,
+                };
+            }
+        }
+        self.move_direction
+    }
+
+    pub fn move_block_left(&mut self) {
+        if let Some(mut moved_block) = self.current_block {
+            moved_block.move_left();
+            if self.fits(&moved_block) {
+                self.current_block.replace(moved_block);
+            }
+        }
+    }
+
+    pub fn move_block_right(&mut self) {
+        if let Some(mut moved_block) = self.current_block {
+            moved_block.move_right();
+            if self.fits(&moved_block) {
+                self.current_block.replace(moved_block);
+            }
+        }
+    }
+
+    /// The whole game state, for saving it across a reset.
+    pub fn to_bytes(&self) -> [u8; STATE_LEN] {
+        let mut state = [0u8; STATE_LEN];
+        state[0] = self.rules.start_level;
+        state[1] = self.rules.clear_threshold as u8;
+        state[2] = self.rules.spawn_delay;
+        state[3] = self.rules.lock_delay;
+        state[4] = self.rules.clear_delay;
+        if let Some(block) = &self.current_block {
+            state[5] = 1;
+            state[6] = block.piece.index() as u8;
+            state[7] = block.rotation;
+            state[8] = block.pos.0 as i8 as u8;
+            state[9] = block.pos.1 as i8 as u8;
+        }
+        state[10] = self.block_cooldown;
+        state[11] = self.lock_timer;
+        state[12..14].copy_from_slice(&self.gravity_acc.to_le_bytes());
+        for (bytes, row) in state[14..78].chunks_mut(2).zip(&self.grid) {
+            bytes.copy_from_slice(&row.to_le_bytes());
+        }
+        let (rng_state, rng_inc) = self.rng.state();
+        state[78..86].copy_from_slice(&rng_state.to_le_bytes());
+        state[86..94].copy_from_slice(&rng_inc.to_le_bytes());
+        state[94] = matches!(self.move_direction, MoveDirection::Right) as u8;
+        state[95..99].copy_from_slice(&self.score.to_le_bytes());
+        state[99..103].copy_from_slice(&self.pieces.to_le_bytes());
+        state[103] = self.has_ended as u8;
+        state[104] = self.rules.randomizer as u8;
+        state[105] = self.bag;
+        state[106] = self.rules.width;
+        state[107] = self.rules.height;
+        state
+    }
+
+    /// Restores a game from `to_bytes`, `None` if the state couldn't have
+    /// come from a running game.
+    pub fn from_bytes(state: &[u8]) -> Option<Self> {
+        if state.len() < STATE_LEN {
+            return None;
+        }
+        let u32_at =
+            |i: usize| u32::from_le_bytes([state[i], state[i + 1], state[i + 2], state[i + 3]]);
+        let u64_at = |i: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&state[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        let mut grid = [0u16; MAX_HEIGHT];
+        for (row, bytes) in grid.iter_mut().zip(state[14..78].chunks(2)) {
+            *row = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+
+        let current_block = match state[5] {
+            0 => None,
+            _ => Some(Block {
+                piece: *Piece::ALL.get(state[6] as usize)?,
+                rotation: state[7],
+                pos: (state[8] as i8 as i32, state[9] as i8 as i32),
+            }),
+        };
+
+        let game = Self {
+            rules: Rules {
+                start_level: state[0],
+                clear_threshold: state[1] as usize,
+                spawn_delay: state[2],
+                lock_delay: state[3],
+                clear_delay: state[4],
+                randomizer: match state[104] {
+                    0 => Randomizer::Uniform,
+                    1 => Randomizer::Bag,
+                    _ => return None,
+                },
+                width: state[106],
+                height: state[107],
+            },
+            current_block,
+            block_cooldown: state[10],
+            lock_timer: state[11],
+            gravity_acc: u16::from_le_bytes([state[12], state[13]]),
+            grid,
+            rng: Rand32::from_state((u64_at(78), u64_at(86))),
+            bag: state[105],
+            move_direction: if state[94] != 0 {
+                MoveDirection::Right
+            } else {
+                MoveDirection::Left
+            },
+            score: u32_at(95),
+            pieces: u32_at(99),
+            has_ended: state[103] != 0,
+            events: Events::default(),
+        };
+
+        // full_row can't take widths out of range, and a zero threshold
+        // would clear rows forever
+        if !(MIN_WIDTH..=MAX_WIDTH).contains(&game.width())
+            || !(MIN_HEIGHT..=MAX_HEIGHT).contains(&game.height())
+            || !(1..=game.width()).contains(&game.rules.clear_threshold)
+        {
+            return None;
+        }
+        let full_row = game.rules.full_row();
+        let in_well = grid
+            .iter()
+            .enumerate()
+            .all(|(y, row)| row & !full_row == 0 && (y < game.height() || *row == 0));
+        // the 4x4 box of a piece in the well overlaps it by at least a cell
+        let block_fits = match &current_block {
+            Some(block) => {
+                block.rotation < 4
+                    && (-3..game.width() as i32).contains(&block.pos.0)
+                    && (-3..game.height() as i32).contains(&block.pos.1)
+                    && game.fits(block)
+            }
+            None => true,
+        };
+        if !in_well
+            || !block_fits
+            || game.rules.start_level as usize >= GRAVITY.len()
+            || game.gravity_acc >= G
+            || game.bag & !FULL_BAG != 0
+        {
+            return None;
+        }
+        Some(game)
+    }
+
     pub const fn has_ended(&self) -> bool {
         self.has_ended
     }
@@ -337,31 +716,58 @@ impl Tetris {
     pub fn reset(&mut self) {
         self.current_block = None;
         self.block_cooldown = 0;
-        self.grid = [bitarr![0; 8]; 32];
+        self.lock_timer = 0;
+        self.gravity_acc = 0;
+        self.grid = [0; MAX_HEIGHT];
         self.rng = Rand32::new(8);
+        self.bag = FULL_BAG;
         self.move_direction = MoveDirection::Left;
         self.score = 0;
+        self.pieces = 0;
         self.has_ended = false;
+        self.events = Events::default();
+    }
+
+    /// `reset` with another sequence of pieces.
+    pub fn reset_seeded(&mut self, seed: u64) {
+        self.reset();
+        self.rng = Rand32::new(seed);
     }
 
     pub fn run(&mut self) {
         if self.has_ended {
             return;
         }
-        if self.current_block.is_some() {
-            if self.reached_bottom() {
-                if self.clipping_top() {
-                    self.has_ended = true;
-                }
-                self.block_to_grid();
-                self.check_line_clears();
+        if self.current_block.is_none() {
+            if self.block_cooldown > 0 {
+                self.block_cooldown -= 1;
             } else {
-                self.fall();
+                self.add_block();
             }
-        } else if self.block_cooldown > 0 {
-            self.block_cooldown -= 1;
-        } else {
-            self.add_block();
+            return;
+        }
+
+        if self.reached_bottom() {
+            // resting on the stack, it locks once the delay is up
+            self.gravity_acc = 0;
+            if self.lock_timer < self.rules.lock_delay {
+                self.lock_timer += 1;
+            } else {
+                self.lock();
+            }
+            return;
+        }
+
+        // sliding off the stack starts the lock delay over
+        self.lock_timer = 0;
+        self.gravity_acc += GRAVITY[self.get_level() as usize];
+        while self.gravity_acc >= G {
+            self.gravity_acc -= G;
+            if self.reached_bottom() {
+                self.gravity_acc = 0;
+                break;
+            }
+            self.fall();
         }
     }
 }
@@ -0,0 +1,77 @@
+use crate::game::TICK_HZ;
+
+/// Ticks without input before the panel is dimmed.
+pub const DIM_AFTER: u32 = 30 * TICK_HZ;
+/// Ticks without input before the panel is turned off and the ticks
+/// slowed to one a second.
+pub const OFF_AFTER: u32 = 120 * TICK_HZ;
+/// Ticks between moves of the static HUD parts.
+pub const SHIFT_EVERY: u32 = 60 * TICK_HZ;
+
+/// What the panel should be doing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Power {
+    On,
+    Dim,
+    Off,
+}
+
+/// Keeps the OLED from burning in: counts the ticks since the last input
+/// and moves the HUD around a 2x2 pixel square over time.
+pub struct Idle {
+    idle_ticks: u32,
+    ticks: u32,
+}
+
+impl Idle {
+    pub const fn new() -> Self {
+        Self {
+            idle_ticks: 0,
+            ticks: 0,
+        }
+    }
+
+    pub const fn power(&self) -> Power {
+        if self.idle_ticks >= OFF_AFTER {
+            Power::Off
+        } else if self.idle_ticks >= DIM_AFTER {
+            Power::Dim
+        } else {
+            Power::On
+        }
+    }
+
+    /// Ticks since the last input, or since the screen last changed by itself.
+    pub const fn idle_ticks(&self) -> u32 {
+        self.idle_ticks
+    }
+
+    /// Step of the HUD shift, 0 to 3.
+    pub const fn shift(&self) -> u8 {
+        ((self.ticks / SHIFT_EVERY) % 4) as u8
+    }
+
+    /// `busy` keeps the panel on while the screen changes by itself.
+    pub fn tick(&mut self, busy: bool) {
+        self.ticks = self.ticks.wrapping_add(1);
+        if busy {
+            self.idle_ticks = 0;
+        } else {
+            self.idle_ticks = self.idle_ticks.saturating_add(1);
+        }
+    }
+
+    /// Restarts the countdown, returns whether the panel was dimmed or
+    /// off, in which case the input should only wake it.
+    pub fn wake(&mut self) -> bool {
+        let was_idle = self.power() != Power::On;
+        self.idle_ticks = 0;
+        was_idle
+    }
+}
+
+impl Default for Idle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,187 @@
+/// How long a button has to be held down before it counts as a hold.
+pub const HOLD_MS: u32 = 500;
+
+/// A second tap starting this soon after the first one ends is a double tap.
+pub const DOUBLE_TAP_MS: u32 = 250;
+
+/// Presses closer than this to the previous release are treated as bounce.
+const DEBOUNCE_MS: u32 = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Button {
+    /// The - button, PB0 / EXTI line 0.
+    Minus,
+    /// The + button, PB1 / EXTI line 1.
+    Plus,
+}
+
+impl Button {
+    const fn index(self) -> usize {
+        match self {
+            Button::Minus => 0,
+            Button::Plus => 1,
+        }
+    }
+
+    pub const fn other(self) -> Self {
+        match self {
+            Button::Minus => Button::Plus,
+            Button::Plus => Button::Minus,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Gesture {
+    /// A button went down on its own.
+    Press(Button),
+    /// A button has been held down for `HOLD_MS`.
+    Hold(Button),
+    /// A button went down again within `DOUBLE_TAP_MS` of a short press.
+    /// The first press has already been reported as a `Press`.
+    DoubleTap(Button),
+    /// A short press that no second one followed within `DOUBLE_TAP_MS`,
+    /// reported once the time is up. For whoever holds a press back until
+    /// it knows it isn't half of a double tap.
+    Tap(Button),
+    /// Both buttons are down at the same time.
+    Chord,
+}
+
+impl Gesture {
+    /// The button the gesture is made with, `None` for the chord.
+    pub const fn button(self) -> Option<Button> {
+        match self {
+            Gesture::Press(button)
+            | Gesture::Hold(button)
+            | Gesture::DoubleTap(button)
+            | Gesture::Tap(button) => Some(button),
+            Gesture::Chord => None,
+        }
+    }
+
+    /// The same gesture with the roles of the buttons swapped.
+    pub const fn swapped(self) -> Self {
+        match self {
+            Gesture::Press(button) => Gesture::Press(button.other()),
+            Gesture::Hold(button) => Gesture::Hold(button.other()),
+            Gesture::DoubleTap(button) => Gesture::DoubleTap(button.other()),
+            Gesture::Tap(button) => Gesture::Tap(button.other()),
+            Gesture::Chord => Gesture::Chord,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ButtonState {
+    down_since: Option<u32>,
+    // `None` until the first release, so nothing counts as bounce before
+    released_at: Option<u32>,
+    // the last release ended a tap that a second one can turn into a double tap
+    tapped: bool,
+    // this press is the second half of a double tap
+    doubled: bool,
+    held: bool,
+}
+
+impl ButtonState {
+    const fn new() -> Self {
+        Self {
+            down_since: None,
+            released_at: None,
+            tapped: false,
+            doubled: false,
+            held: false,
+        }
+    }
+}
+
+/// Turns raw button edges into gestures.
+///
+/// Timestamps are `Clock::now_ms` readings and may wrap around.
+pub struct ButtonTracker {
+    buttons: [ButtonState; 2],
+    chorded: bool,
+}
+
+impl ButtonTracker {
+    pub const fn new() -> Self {
+        Self {
+            buttons: [ButtonState::new(); 2],
+            chorded: false,
+        }
+    }
+
+    /// Feeds a button edge, returns the gesture it completes, if any.
+    pub fn update(&mut self, button: Button, pressed: bool, now_ms: u32) -> Option<Gesture> {
+        let other_down = self.buttons[button.other().index()].down_since.is_some();
+        let state = &mut self.buttons[button.index()];
+
+        if pressed {
+            let bounce = match state.released_at {
+                Some(at) => now_ms.wrapping_sub(at) < DEBOUNCE_MS,
+                None => false,
+            };
+            if state.down_since.is_some() || bounce {
+                return None;
+            }
+            let double = match state.released_at {
+                Some(at) => state.tapped && now_ms.wrapping_sub(at) < DOUBLE_TAP_MS,
+                None => false,
+            };
+            state.down_since = Some(now_ms);
+            state.held = false;
+            state.tapped = false;
+            state.doubled = false;
+
+            if other_down {
+                self.chorded = true;
+                return Some(Gesture::Chord);
+            }
+            if double {
+                state.doubled = true;
+                return Some(Gesture::DoubleTap(button));
+            }
+            Some(Gesture::Press(button))
+        } else {
+            if state.down_since.take().is_some() {
+                state.released_at = Some(now_ms);
+                // holds, chords and the second tap don't start a new double tap
+                state.tapped = !state.held && !state.doubled && !self.chorded;
+            }
+            if !other_down {
+                self.chorded = false;
+            }
+            None
+        }
+    }
+
+    /// Checks for buttons that have been held long enough and taps that
+    /// weren't doubled in time, call this periodically.
+    pub fn poll(&mut self, now_ms: u32) -> Option<Gesture> {
+        if self.chorded {
+            return None;
+        }
+        for button in [Button::Minus, Button::Plus] {
+            let state = &mut self.buttons[button.index()];
+            match (state.down_since, state.released_at) {
+                (Some(since), _) if !state.held && now_ms.wrapping_sub(since) >= HOLD_MS => {
+                    state.held = true;
+                    return Some(Gesture::Hold(button));
+                }
+                (None, Some(at)) if state.tapped && now_ms.wrapping_sub(at) >= DOUBLE_TAP_MS => {
+                    state.tapped = false;
+                    return Some(Gesture::Tap(button));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl Default for ButtonTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
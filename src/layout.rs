@@ -0,0 +1,231 @@
+use crate::game::{MAX_HEIGHT, MIN_HEIGHT, MIN_WIDTH};
+use crate::settings::Settings;
+
+// The well starts after the score box, the top two rows
+// (where pieces spawn) hide behind its border.
+const WELL_TOP: i32 = 2 * 16 - 1;
+
+// Size of the score box along the long edge of the screen.
+const HUD_LENGTH: u32 = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Orientation {
+    /// 16x96 with the tip pointing left, held in the right hand.
+    Portrait,
+    /// 16x96 turned around, held in the left hand.
+    PortraitFlipped,
+    /// The panel's native 96x16, the well lies on its side.
+    Landscape,
+    /// 96x16 turned around, held in the left hand.
+    LandscapeFlipped,
+}
+
+// Pixels across the screen, in either orientation.
+const ACROSS: i32 = 16;
+// Pixels along the screen from the top of the well to the end.
+const WELL_LENGTH: i32 = 96 - WELL_TOP;
+
+/// Largest cell width and height that still leave room for the smallest well.
+pub const MAX_CELL: (u8, u8) = (
+    (ACROSS / MIN_WIDTH as i32) as u8,
+    (WELL_LENGTH / MIN_HEIGHT as i32) as u8,
+);
+
+/// Maps the board, HUD and text onto screen coordinates.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    orientation: Orientation,
+    // pixels of a board cell across and along the screen
+    cell: (i32, i32),
+    // pixels left of the first column, centering a narrow well
+    margin: i32,
+    // columns and rows of the well, and the most that fit with these cells
+    board: (u8, u8),
+    room: (u8, u8),
+    // pixels the HUD is moved across and along the screen
+    shift: (i32, i32),
+}
+
+impl Layout {
+    pub const fn new(settings: &Settings) -> Self {
+        let orientation = match (settings.landscape, settings.left_handed) {
+            (false, false) => Orientation::Portrait,
+            (false, true) => Orientation::PortraitFlipped,
+            (true, false) => Orientation::Landscape,
+            (true, true) => Orientation::LandscapeFlipped,
+        };
+        let cell = (
+            clamp(settings.cell_width as i32, 1, MAX_CELL.0 as i32),
+            clamp(settings.cell_height as i32, 1, MAX_CELL.1 as i32),
+        );
+        let room = (
+            ACROSS / cell.0,
+            clamp(WELL_LENGTH / cell.1, MIN_HEIGHT as i32, MAX_HEIGHT as i32),
+        );
+        let board = (
+            clamp(settings.columns as i32, MIN_WIDTH as i32, room.0),
+            clamp(settings.rows as i32, MIN_HEIGHT as i32, room.1),
+        );
+        Self {
+            orientation,
+            cell,
+            margin: (ACROSS - board.0 * cell.0) / 2,
+            board: (board.0 as u8, board.1 as u8),
+            room: (room.0 as u8, room.1 as u8),
+            shift: (0, 0),
+        }
+    }
+
+    /// Moves the HUD and the text screens by a pixel in a square over
+    /// `step` 0 to 3, so their edges and text don't burn in. The box only
+    /// moves along the screen, across it there's no room.
+    pub const fn shifted(self, step: u8) -> Self {
+        let shift = match step % 4 {
+            0 => (0, 0),
+            1 => (1, 0),
+            2 => (1, 1),
+            _ => (0, 1),
+        };
+        Self { shift, ..self }
+    }
+
+    /// Columns and rows of the well as set, cut down to what fits.
+    pub const fn board(&self) -> (u8, u8) {
+        self.board
+    }
+
+    /// The most columns and rows that fit the screen with these cells.
+    pub const fn room(&self) -> (u8, u8) {
+        self.room
+    }
+
+    pub const fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    // The display controller does the 180 degree turns,
+    // so flipped orientations share the math.
+    pub const fn is_landscape(&self) -> bool {
+        matches!(
+            self.orientation,
+            Orientation::Landscape | Orientation::LandscapeFlipped
+        )
+    }
+
+    /// Top left pixel of the board cell in column `x` and row `y`.
+    pub const fn cell(&self, x: i32, y: i32) -> (i32, i32) {
+        let (across, along) = self.cell;
+        if self.is_landscape() {
+            // the portrait picture turned a quarter counterclockwise,
+            // so pieces keep their handedness and fall to the right
+            (
+                y * along + WELL_TOP,
+                ACROSS - self.margin - across - x * across,
+            )
+        } else {
+            (self.margin + x * across, y * along + WELL_TOP)
+        }
+    }
+
+    /// Width and height of a board cell on the screen.
+    pub const fn cell_size(&self) -> (u32, u32) {
+        let (across, along) = self.cell;
+        if self.is_landscape() {
+            (along as u32, across as u32)
+        } else {
+            (across as u32, along as u32)
+        }
+    }
+
+    /// Position and size of the score box.
+    pub const fn hud(&self) -> ((i32, i32), (u32, u32)) {
+        let along = self.shift.1;
+        if self.is_landscape() {
+            ((along, 0), (HUD_LENGTH, 16))
+        } else {
+            ((0, along), (16, HUD_LENGTH))
+        }
+    }
+
+    /// Center of the score text inside the score box.
+    pub const fn score(&self) -> (i32, i32) {
+        let (across, along) = self.shift;
+        if self.is_landscape() {
+            (HUD_LENGTH as i32 / 2 + along, 10 - across)
+        } else {
+            (8 + across, 15 + along)
+        }
+    }
+
+    /// Pixels the score can take in one row. Portrait only has the inside
+    /// of the box, in landscape it goes between the power bars and the
+    /// arrow.
+    pub const fn score_room(&self) -> i32 {
+        if self.is_landscape() {
+            15
+        } else {
+            14
+        }
+    }
+
+    /// Centers of the two rows of a score too long for one, stacked
+    /// where the one row would go.
+    pub const fn score_stacked(&self) -> ((i32, i32), (i32, i32)) {
+        let (x, y) = self.score();
+        ((x, y - 3), (x, y + 3))
+    }
+
+    /// Top left of the power indicator inside the score box, its bars
+    /// run across the screen.
+    pub const fn supply(&self) -> (i32, i32) {
+        let along = self.shift.1;
+        if self.is_landscape() {
+            (3 + along, 4)
+        } else {
+            (4, 3 + along)
+        }
+    }
+
+    /// Center of the move direction arrow, below the score.
+    pub const fn arrow(&self) -> (i32, i32) {
+        let (across, along) = self.shift;
+        if self.is_landscape() {
+            (HUD_LENGTH as i32 - 5 + along, 8 - across)
+        } else {
+            (8 + across, HUD_LENGTH as i32 - 6 + along)
+        }
+    }
+
+    /// The screen direction of a step to the right on the board.
+    pub const fn right(&self) -> (i32, i32) {
+        if self.is_landscape() {
+            (0, -1)
+        } else {
+            (1, 0)
+        }
+    }
+
+    /// Center of the `n`th line of a text screen.
+    ///
+    /// Lines come in pairs (label and value) of up to 4 characters.
+    /// Portrait stacks them down the screen, landscape puts each pair
+    /// in its own column.
+    pub const fn line(&self, n: i32) -> (i32, i32) {
+        let (across, along) = self.shift;
+        if self.is_landscape() {
+            (8 + 16 * (n / 2) + along, 6 + 7 * (n % 2) - across)
+        } else {
+            (8 + across, 13 + 8 * n + 4 * (n / 2) + along)
+        }
+    }
+}
+
+const fn clamp(value: i32, min: i32, max: i32) -> i32 {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
@@ -0,0 +1,18 @@
+#![no_std]
+
+pub mod ai;
+pub mod animation;
+pub mod app;
+pub mod clock;
+pub mod controls;
+pub mod digits;
+pub mod double_buffer;
+pub mod frame;
+pub mod game;
+pub mod idle;
+pub mod input;
+pub mod layout;
+pub mod record;
+pub mod render;
+pub mod settings;
+pub mod supply;
@@ -1,88 +1,183 @@
 #![no_std]
 #![no_main]
-
-mod game;
-
-use embedded_graphics::mono_font::ascii::FONT_4X6;
-use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::text::{Alignment, Text};
-use game::Tetris;
-use numtoa::NumToA;
-
-use core::cell::RefCell;
+#![feature(panic_info_message)]
+
+mod adc;
+mod checkpoint;
+mod crash_i2c;
+mod display_link;
+mod flash;
+mod oled;
+mod panic;
+mod watchdog;
+
+use adc::VinAdc;
+use display_interface::DisplayError;
+use display_link::TRANSFER_LEN;
+use flash::Storage;
+use oled::{setup_i2c, Oled};
+use pinecil_tetris::app::{App, ResetCause};
+use pinecil_tetris::clock::{Clock, Interval};
+use pinecil_tetris::double_buffer::DoubleBuffer;
+use pinecil_tetris::frame::Framebuffer;
+use pinecil_tetris::game::TICK_HZ;
+use pinecil_tetris::idle::Power;
+use pinecil_tetris::input::{Button, ButtonTracker};
+use pinecil_tetris::render;
+use pinecil_tetris::supply::SupplyMonitor;
+use watchdog::Watchdog;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use heapless::mpmc::{Q16, Q2};
 
 use hal::eclic::{EclicExt, Level, LevelPriorityBits};
 use hal::exti::{ExtiEvent, ExtiLine, TriggerEdge};
-use hal::timer::{Event, Timer};
-use riscv::_export::critical_section;
-use riscv::_export::critical_section::Mutex;
+use hal::timer::{Event as TimerEvent, Timer};
 
-use panic_halt as _;
-
-use embedded_hal::digital::v2::OutputPin;
 use gd32vf103_pac as pac;
 use gd32vf103xx_hal::{self as hal, prelude::*};
 use hal::delay::McycleDelay;
 
-use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306};
-
-use embedded_graphics::{
-    pixelcolor::BinaryColor,
-    prelude::*,
-    primitives::{PrimitiveStyle, Rectangle},
-};
-
-type I2cInterfaceTypeAlias = I2CInterface<
-    hal::i2c::BlockingI2c<
-        pac::I2C0,
-        (
-            hal::gpio::gpiob::PB6<hal::gpio::Alternate<hal::gpio::OpenDrain>>,
-            hal::gpio::gpiob::PB7<hal::gpio::Alternate<hal::gpio::OpenDrain>>,
-        ),
-    >,
->;
-type DisplayTypeAlias = Ssd1306<
-    I2cInterfaceTypeAlias,
-    DisplaySize96x16,
-    ssd1306::mode::BufferedGraphicsMode<DisplaySize96x16>,
->;
-
-static G_DISP: Mutex<RefCell<Option<DisplayTypeAlias>>> = Mutex::new(RefCell::new(None));
-static G_GAME: Mutex<RefCell<Option<Tetris>>> = Mutex::new(RefCell::new(None));
-static G_TIMER1: Mutex<RefCell<Option<Timer<pac::TIMER1>>>> = Mutex::new(RefCell::new(None));
-static G_DELAY: Mutex<RefCell<Option<McycleDelay>>> = Mutex::new(RefCell::new(None));
+const SYSCLK_HZ: u32 = 96_000_000;
+
+// How often the game in progress is checkpointed for the watchdog.
+const CHECKPOINT_MS: u32 = 1000;
+
+// How often the input voltage is sampled. The filter in `SupplyMonitor`
+// settles over a few samples, so a drop is caught within a fraction of
+// a second however busy the main loop is.
+const SUPPLY_MS: u32 = 50;
+
+#[derive(Clone, Copy)]
+enum Event {
+    Tick,
+    Button {
+        button: Button,
+        pressed: bool,
+        at_ms: u32,
+    },
+}
+
+// The only state shared between contexts. Everything else is owned by
+// exactly one of them:
+//
+// - main (thread mode): app, buttons, display, flash, ADC, watchdog and
+//   delay; renders and does the slow I2C and flash work
+// - TIMER1 (level 1): owns the tick timer once main handed it over, pushes
+//   ticks; slowed to 1 Hz while the panel is off
+// - EXTI_LINE0/1 (level 2, preempts TIMER1): pushes timestamped button
+//   edges, only touches its own pending flag
+//
+// Filled by the interrupt handlers, drained by the main loop.
+static EVENTS: Q16<Event> = Q16::new();
+
+// Hands the tick timer from main to TIMER1, which takes it out on every
+// tick and puts it back for the next one. Nothing else dequeues it.
+static TICKER: Q2<Ticker> = Q2::new();
+
+// Ticks a second main wants, TIMER1 switches over on its next tick.
+static TICK_RATE: AtomicU32 = AtomicU32::new(TICK_HZ);
+
+// The APB1 clock, for the panic handler to time its own I2C by. The
+// internal 8 MHz oscillator runs it until main switches to the PLL.
+static APB1_HZ: AtomicU32 = AtomicU32::new(8_000_000);
+
+// The tick timer and the rate it runs at.
+struct Ticker {
+    timer: Timer<pac::TIMER1>,
+    hz: u32,
+}
+
+impl Ticker {
+    fn tick(&mut self) {
+        self.timer.clear_update_interrupt_flag();
+        let hz = TICK_RATE.load(Ordering::Relaxed);
+        if hz != self.hz {
+            self.hz = hz;
+            self.timer.start(hz.hz());
+        }
+    }
+}
+
+// The core timer's counter, `mtime`, in the TIMER unit of the Bumblebee
+// core. It runs off the AHB clock, which sleep mode leaves running, so it
+// keeps counting through wfi where the cycle counter stops with the core.
+const MTIME_LO: *const u32 = 0xD100_0000 as *const u32;
+const MTIME_HI: *const u32 = 0xD100_0004 as *const u32;
+
+// `mtime` counts at a quarter of the AHB clock.
+const MTIME_HZ: u32 = SYSCLK_HZ / 4;
+
+// Milliseconds since boot from the core timer. Usable from any context,
+// it only reads the counter.
+#[derive(Clone, Copy)]
+struct MtimeClock;
+
+impl Clock for MtimeClock {
+    fn now_ms(&self) -> u32 {
+        // the low half may carry into the high one between the reads
+        let ticks = loop {
+            let hi = unsafe { MTIME_HI.read_volatile() };
+            let lo = unsafe { MTIME_LO.read_volatile() };
+            if unsafe { MTIME_HI.read_volatile() } == hi {
+                break (hi as u64) << 32 | lo as u64;
+            }
+        };
+        (ticks / (MTIME_HZ / 1000) as u64) as u32
+    }
+}
+
+// Buttons are pulled low and read high while pressed.
+fn is_pressed(button: Button) -> bool {
+    let pin = match button {
+        Button::Minus => 0,
+        Button::Plus => 1,
+    };
+    unsafe { (*pac::GPIOB::ptr()).istat.read().bits() & (1 << pin) != 0 }
+}
 
 #[riscv_rt::entry]
 fn main() -> ! {
     let p = pac::Peripherals::take().unwrap();
 
-    let game = Tetris::init();
-
-    critical_section::with(|cs| {
-        G_GAME.borrow(cs).replace(Some(game));
-    });
+    let mut storage = Storage::new(p.FMC);
+    let settings = storage.load_settings();
+
+    let mut app = App::init(settings);
+    let reset_cause = watchdog::take_reset_cause();
+    app.diagnostics_mut().reset_cause = reset_cause;
+    // a game saved before the power dropped or checkpointed before the
+    // watchdog had to step in, it starts out paused
+    app.resume(&storage.load_game());
+    storage.clear_game();
+    if let Some(record) = checkpoint::take() {
+        if reset_cause == ResetCause::Watchdog {
+            app.resume(&record);
+        }
+    }
+    let mut buttons = ButtonTracker::new();
 
     // Use external 8MHz HXTAL and set PLL to get 96MHz system clock.
     let mut rcu = p
         .RCU
         .configure()
         .ext_hf_clock(8.mhz())
-        .sysclk(96.mhz())
+        .sysclk(SYSCLK_HZ.hz())
         .freeze();
+    APB1_HZ.store(rcu.clocks.pclk1().0, Ordering::Relaxed);
     let mut afio = p.AFIO.constrain(&mut rcu);
 
     let mut delay = McycleDelay::new(&rcu.clocks);
-    critical_section::with(|cs| {
-        G_DELAY.borrow(cs).replace(Some(delay));
-    });
 
     let gpioa = p.GPIOA.split(&mut rcu);
     let gpiob = p.GPIOB.split(&mut rcu);
 
-    // left + button
+    let mut supply = SupplyMonitor::new(VinAdc::new(p.ADC0, gpioa.pa0.into_analog()));
+
+    // left - button
     let btn_b = gpiob.pb0.into_pull_down_input();
 
-    // right - button
+    // right + button
     // Note that this pin is already pulled low externally via a 10K resistor
     // since it also operates the BOOT0 pin, so we don't need the internal
     // pull-down.
@@ -92,44 +187,46 @@ fn main() -> ! {
     pac::ECLIC::set_threshold_level(Level::L0);
     pac::ECLIC::set_level_priority_bits(LevelPriorityBits::L2P2);
 
-    // timer 1 interrupt
+    // timer 1 interrupt, lowest level so button timestamps stay accurate
     pac::ECLIC::setup(
         pac::Interrupt::TIMER1,
         hal::eclic::TriggerType::Level,
         hal::eclic::Level::L1,
         hal::eclic::Priority::P1,
     );
-    let mut timer1 = Timer::timer1(p.TIMER1, 4.hz(), &mut rcu);
-    timer1.listen(Event::Update);
-    critical_section::with(|cs| {
-        G_TIMER1.borrow(cs).replace(Some(timer1));
+    let mut timer1 = Timer::timer1(p.TIMER1, TICK_HZ.hz(), &mut rcu);
+    timer1.listen(TimerEvent::Update);
+    // the queue is empty, it always takes the one timer
+    let _ = TICKER.enqueue(Ticker {
+        timer: timer1,
+        hz: TICK_HZ,
     });
 
     let mut exti = hal::exti::Exti::new(p.EXTI);
 
-    // + button EXTI interrupt
+    // + button EXTI interrupt, both edges so releases can be tracked
     pac::ECLIC::setup(
         pac::Interrupt::EXTI_LINE1,
         hal::eclic::TriggerType::RisingEdge,
-        hal::eclic::Level::L1,
-        hal::eclic::Priority::P2,
+        hal::eclic::Level::L2,
+        hal::eclic::Priority::P1,
     );
     afio.extiss(btn_a.port(), btn_a.pin_number());
     let extiline_a = ExtiLine::from_gpio_line(btn_a.pin_number()).unwrap();
-    exti.listen(extiline_a, TriggerEdge::Rising);
+    exti.listen(extiline_a, TriggerEdge::Both);
     exti.gen_event(extiline_a, ExtiEvent::Enable);
     hal::exti::Exti::clear(extiline_a);
 
-    // - button EXTI interrupt
+    // - button EXTI interrupt, both edges so releases can be tracked
     pac::ECLIC::setup(
         pac::Interrupt::EXTI_LINE0,
         hal::eclic::TriggerType::RisingEdge,
-        hal::eclic::Level::L1,
-        hal::eclic::Priority::P2,
+        hal::eclic::Level::L2,
+        hal::eclic::Priority::P1,
     );
     afio.extiss(btn_b.port(), btn_b.pin_number());
     let extiline_b = ExtiLine::from_gpio_line(btn_b.pin_number()).unwrap();
-    exti.listen(extiline_b, TriggerEdge::Rising);
+    exti.listen(extiline_b, TriggerEdge::Both);
     exti.gen_event(extiline_b, ExtiEvent::Enable);
     hal::exti::Exti::clear(extiline_b);
 
@@ -141,182 +238,204 @@ fn main() -> ! {
     };
 
     // OLED reset: Pull low to reset.
-    let mut oled_reset = gpioa
+    let oled_reset = gpioa
         .pa9
         .into_push_pull_output_with_state(hal::gpio::State::Low);
 
     let pb6_scl = gpiob.pb6.into_alternate_open_drain();
     let pb7_sda = gpiob.pb7.into_alternate_open_drain();
 
-    // Set up i2c.
-    let i2c0 = hal::i2c::BlockingI2c::i2c0(
-        p.I2C0,
-        (pb6_scl, pb7_sda),
-        &mut afio,
-        hal::i2c::Mode::Fast {
-            frequency: 400_000.hz(),
-            duty_cycle: hal::i2c::DutyCycle::Ratio2to1,
-        },
-        &mut rcu,
-        1000,
-        10,
-        1000,
-        1000,
-    );
+    let i2c0 = setup_i2c(p.I2C0, pb6_scl, pb7_sda, &mut afio, &mut rcu);
 
     // OLED datasheet recommends 100 ms delay on power up.
     delay.delay_ms(100);
 
-    // Init OLED.
-    oled_reset.set_high().unwrap();
-
-    // OLED datasheet recommends 3 us delay to wait for init.
-    delay.delay_us(3);
+    // read by the DMA while a frame is sent, owned by the display link;
+    // handed out once, nothing else can get at it
+    let buffers = riscv::singleton!(: DoubleBuffer<TRANSFER_LEN> = DoubleBuffer::new()).unwrap();
+    let mut oled = Oled::new(i2c0, p.DMA0, buffers, oled_reset, delay);
+    let mut frame = Framebuffer::new(true);
+    if oled.init(&settings, &mut frame).is_err() {
+        recover(&mut app, &mut oled, &mut frame, &mut afio, &mut rcu);
+    }
 
-    let interface = I2CDisplayInterface::new(i2c0);
-    let mut disp = Ssd1306::new(interface, DisplaySize96x16, DisplayRotation::Rotate90)
-        .into_buffered_graphics_mode();
-    disp.init().unwrap();
+    // a crash screen that was never acknowledged, the reset came from
+    // somewhere else
+    if let Some(message) = panic::take_last_panic() {
+        render::draw_crash(&message, &mut frame).unwrap();
+        if oled.flush(&mut frame).is_err() {
+            recover(&mut app, &mut oled, &mut frame, &mut afio, &mut rcu);
+        }
+        loop {
+            match EVENTS.dequeue() {
+                Some(Event::Button { pressed: true, .. }) => break,
+                Some(_) => {}
+                None => unsafe { riscv::asm::wfi() },
+            }
+        }
+        while EVENTS.dequeue().is_some() {}
+    }
 
-    critical_section::with(|cs| {
-        G_DISP.borrow(cs).replace(Some(disp));
-    });
+    let mut redraw = true;
+    let mut power = Power::On;
+    let mut checkpoint_timer = Interval::new(CHECKPOINT_MS, MtimeClock.now_ms());
+    let mut supply_timer = Interval::new(SUPPLY_MS, MtimeClock.now_ms());
+    // what was saved to flash when the supply last turned low
+    let mut saved_game = None;
 
-    // disp.set_brightness(Brightness::custom(0xF1, 0x0F_u8));
+    let mut watchdog = Watchdog::start(p.FWDGT);
 
     loop {
-        unsafe {
-            riscv::asm::wfi();
-        }
-    }
-}
+        watchdog.feed();
 
-fn draw(game: &mut Tetris, disp: &mut DisplayTypeAlias) {
-    disp.clear();
-
-    let thin_stroke = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
-
-    let grid = game.get_grid();
-
-    let character_style = MonoTextStyle::new(&FONT_4X6, BinaryColor::On);
-
-    let mut buf = [0u8; 20];
-
-    if game.has_ended() {
-        Text::with_alignment(
-            "Game",
-            Point::new(8, 13),
-            character_style,
-            Alignment::Center,
-        )
-        .draw(disp)
-        .unwrap();
-
-        Text::with_alignment(
-            "over",
-            Point::new(8, 23),
-            character_style,
-            Alignment::Center,
-        )
-        .draw(disp)
-        .unwrap();
-
-        Text::with_alignment("Pts", Point::new(8, 43), character_style, Alignment::Center)
-            .draw(disp)
-            .unwrap();
-
-        Text::with_alignment(
-            game.get_score().numtoa_str(10, &mut buf),
-            Point::new(8, 53),
-            character_style,
-            Alignment::Center,
-        )
-        .draw(disp)
-        .unwrap();
-
-        disp.flush().unwrap();
-        return;
-    }
+        while let Some(event) = EVENTS.dequeue() {
+            handle_event(event, &mut app, &mut buttons);
+            redraw = true;
+        }
 
-    const VERT_OFFSET: i32 = 2 * 16;
-
-    Rectangle::new(Point::new(0, 0), Size::new(1, 32))
-        .into_styled(thin_stroke)
-        .draw(disp)
-        .unwrap();
-
-    Rectangle::new(Point::new(0, 0), Size::new(16, 1))
-        .into_styled(thin_stroke)
-        .draw(disp)
-        .unwrap();
-
-    Rectangle::new(Point::new(15, 0), Size::new(1, 32))
-        .into_styled(thin_stroke)
-        .draw(disp)
-        .unwrap();
-
-    Rectangle::new(Point::new(0, 31), Size::new(16, 1))
-        .into_styled(thin_stroke)
-        .draw(disp)
-        .unwrap();
-
-    Text::with_alignment(
-        game.get_score().numtoa_str(10, &mut buf),
-        Point::new(8, 15),
-        character_style,
-        Alignment::Center,
-    )
-    .draw(disp)
-    .unwrap();
-
-    if let Some(block) = game.get_block() {
-        for (i, row) in block.shape.iter().enumerate() {
-            for (j, bit) in row.iter().enumerate() {
-                let x: i32 = (j as i32 + block.pos.0) * 2;
-                // let y: i32 = 95 - (i as i32 + block.pos.1 as i32) * 2 - 1;
-                let y: i32 = (i as i32 + block.pos.1) * 2 - 1 + VERT_OFFSET;
-                if *bit {
-                    Rectangle::new(Point::new(x, y), Size::new(2, 2))
-                        .into_styled(thin_stroke)
-                        .draw(disp)
-                        .unwrap();
+        // save while the regulator still holds up
+        if supply_timer.elapsed(&MtimeClock) {
+            let turned_low = supply.update();
+            if let Some(reading) = supply.supply() {
+                app.set_supply(reading);
+            }
+            if turned_low {
+                if let Some(record) = app.save() {
+                    storage.save_game(&record);
+                    saved_game = Some(record);
                 }
             }
+            // once the game went on with the supply back up, or ended, the
+            // save is stale and mustn't come back at the next boot
+            if let Some(record) = saved_game {
+                let low = supply.supply().map_or(false, |reading| reading.low);
+                let current = app.save();
+                if current.is_none() || (current != Some(record) && !low) {
+                    storage.clear_game();
+                    saved_game = None;
+                }
+            }
+        }
+
+        if checkpoint_timer.elapsed(&MtimeClock) {
+            match app.save() {
+                Some(record) => checkpoint::store(&record),
+                None => checkpoint::clear(),
+            }
+        }
+
+        if app.power() != power {
+            power = app.power();
+            if oled.set_power(power, app.settings()).is_err() {
+                recover(&mut app, &mut oled, &mut frame, &mut afio, &mut rcu);
+            }
+            // with the panel off nothing changes until a button is
+            // pressed, so wfi sleeps through all but the one tick a
+            // second that keeps the watchdog fed. Speeding back up takes
+            // until that tick, the press that woke the panel doesn't wait.
+            //
+            // That is all the power saving there is: the MCU only ever
+            // sleeps in wfi with its clocks running, it is never put into
+            // deep-sleep. The watchdog counts on through deep-sleep and
+            // can't be stopped, so that would take an RTC alarm to wake
+            // up for it, and the PLL brought back on every wakeup.
+            let hz = if power == Power::Off { 1 } else { TICK_HZ };
+            TICK_RATE.store(hz, Ordering::Relaxed);
+        }
+
+        if let Some(settings) = app.take_saved_settings() {
+            if oled.apply_settings(&settings, &mut frame).is_err() {
+                recover(&mut app, &mut oled, &mut frame, &mut afio, &mut rcu);
+            }
+            storage.save_settings(&settings);
+        }
+
+        // at most one frame for everything that happened since the last one
+        if redraw {
+            if draw(&app, &mut oled, &mut frame).is_err() {
+                recover(&mut app, &mut oled, &mut frame, &mut afio, &mut rcu);
+            }
+            redraw = false;
+            continue;
+        }
+
+        // An event queued after the check above would otherwise wait for
+        // the next interrupt, wfi still wakes up with interrupts disabled.
+        unsafe { riscv::interrupt::disable() };
+        let event = EVENTS.dequeue();
+        if event.is_none() {
+            unsafe { riscv::asm::wfi() };
+        }
+        unsafe { riscv::interrupt::enable() };
+
+        if let Some(event) = event {
+            handle_event(event, &mut app, &mut buttons);
+            redraw = true;
         }
     }
+}
 
-    for (i, row) in grid.iter().enumerate() {
-        for (j, val) in row.iter().enumerate() {
-            let x: i32 = (j as i32) * 2;
-            // let y: i32 = 95 - (i as i32) * 2 - 1;
-            let y: i32 = (i as i32) * 2 - 1 + VERT_OFFSET;
-            if *val {
-                Rectangle::new(Point::new(x, y), Size::new(2, 2))
-                    .into_styled(thin_stroke)
-                    .draw(disp)
-                    .unwrap();
+fn handle_event(event: Event, app: &mut App, buttons: &mut ButtonTracker) {
+    match event {
+        Event::Tick => {
+            if let Some(gesture) = buttons.poll(MtimeClock.now_ms()) {
+                app.handle(gesture);
+            }
+            app.tick();
+        }
+        Event::Button {
+            button,
+            pressed,
+            at_ms,
+        } => {
+            if let Some(gesture) = buttons.update(button, pressed, at_ms) {
+                app.handle(gesture);
             }
         }
     }
-    disp.flush().unwrap();
+}
+
+// Counts the failure and starts the display over. If that fails too the
+// next frame will try again, the game keeps running in the meantime.
+fn recover(
+    app: &mut App,
+    oled: &mut Oled,
+    frame: &mut Framebuffer,
+    afio: &mut hal::afio::Afio,
+    rcu: &mut hal::rcu::Rcu,
+) {
+    let diagnostics = app.diagnostics_mut();
+    diagnostics.display_errors += 1;
+    diagnostics.display_resets += 1;
+    if oled.recover(app.settings(), frame, afio, rcu).is_err() {
+        app.diagnostics_mut().display_errors += 1;
+    }
+}
+
+fn draw(app: &App, oled: &mut Oled, frame: &mut Framebuffer) -> Result<(), DisplayError> {
+    frame.clear_buffer();
+    render::draw(app, frame).unwrap();
+    oled.flush(frame)
+}
+
+// The queue only fills up if the main loop is stuck, dropping is all we can do then.
+fn push_button(button: Button) {
+    let _ = EVENTS.enqueue(Event::Button {
+        button,
+        pressed: is_pressed(button),
+        at_ms: MtimeClock.now_ms(),
+    });
 }
 
 #[allow(non_snake_case)]
 #[no_mangle]
 fn TIMER1() {
-    critical_section::with(|cs| {
-        if let Some(timer1) = &mut *G_TIMER1.borrow(cs).borrow_mut() {
-            timer1.clear_update_interrupt_flag();
-        }
-
-        if let Some(game) = &mut *G_GAME.borrow(cs).borrow_mut() {
-            game.run();
+    if let Some(mut ticker) = TICKER.dequeue() {
+        ticker.tick();
+        let _ = TICKER.enqueue(ticker);
+    }
 
-            if let Some(disp) = &mut *G_DISP.borrow(cs).borrow_mut() {
-                draw(game, disp);
-            }
-        }
-    });
+    let _ = EVENTS.enqueue(Event::Tick);
 }
 
 #[allow(non_snake_case)]
@@ -328,19 +447,7 @@ fn EXTI_LINE0() {
         hal::exti::Exti::clear(extiline);
     }
 
-    critical_section::with(|cs| {
-        if let Some(game) = &mut *G_GAME.borrow(cs).borrow_mut() {
-            if game.has_ended() {
-                game.reset();
-            } else {
-                game.rotate_block();
-
-                if let Some(disp) = &mut *G_DISP.borrow(cs).borrow_mut() {
-                    draw(game, disp);
-                }
-            }
-        }
-    });
+    push_button(Button::Minus);
 }
 
 #[allow(non_snake_case)]
@@ -352,17 +459,5 @@ fn EXTI_LINE1() {
         hal::exti::Exti::clear(extiline);
     }
 
-    critical_section::with(|cs| {
-        if let Some(game) = &mut *G_GAME.borrow(cs).borrow_mut() {
-            if game.has_ended() {
-                game.reset();
-            } else {
-                game.move_block();
-
-                if let Some(disp) = &mut *G_DISP.borrow(cs).borrow_mut() {
-                    draw(game, disp);
-                }
-            }
-        }
-    });
+    push_button(Button::Plus);
 }
@@ -0,0 +1,212 @@
+use display_interface::DisplayError;
+use embedded_hal::blocking::delay::{DelayMs, DelayUs};
+use embedded_hal::digital::v2::OutputPin;
+use gd32vf103_pac as pac;
+use gd32vf103xx_hal::{self as hal, prelude::*};
+use hal::delay::McycleDelay;
+use hal::gpio::gpioa::PA9;
+use hal::gpio::gpiob::{PB6, PB7};
+use hal::gpio::{Alternate, OpenDrain, Output, PushPull};
+use pinecil_tetris::double_buffer::DoubleBuffer;
+use pinecil_tetris::frame::{Area, Framebuffer};
+use pinecil_tetris::idle::Power;
+use pinecil_tetris::layout::{Layout, Orientation};
+use pinecil_tetris::settings::Settings;
+use ssd1306::{prelude::*, Ssd1306};
+
+use crate::display_link::{DisplayLink, TRANSFER_LEN};
+
+pub type I2cBus =
+    hal::i2c::BlockingI2c<pac::I2C0, (PB6<Alternate<OpenDrain>>, PB7<Alternate<OpenDrain>>)>;
+type I2cInterfaceTypeAlias = DisplayLink<I2cBus>;
+// Frames are kept in our own `Framebuffer` and go out through the link, the
+// driver only sends commands.
+type DisplayTypeAlias = Ssd1306<I2cInterfaceTypeAlias, DisplaySize96x16, ssd1306::mode::BasicMode>;
+
+pub fn setup_i2c(
+    i2c0: pac::I2C0,
+    scl: PB6<Alternate<OpenDrain>>,
+    sda: PB7<Alternate<OpenDrain>>,
+    afio: &mut hal::afio::Afio,
+    rcu: &mut hal::rcu::Rcu,
+) -> I2cBus {
+    hal::i2c::BlockingI2c::i2c0(
+        i2c0,
+        (scl, sda),
+        afio,
+        hal::i2c::Mode::Fast {
+            frequency: 400_000.hz(),
+            duty_cycle: hal::i2c::DutyCycle::Ratio2to1,
+        },
+        rcu,
+        1000,
+        10,
+        1000,
+        1000,
+    )
+}
+
+pub fn rotation(layout: &Layout) -> DisplayRotation {
+    match layout.orientation() {
+        Orientation::Portrait => DisplayRotation::Rotate90,
+        Orientation::PortraitFlipped => DisplayRotation::Rotate270,
+        Orientation::Landscape => DisplayRotation::Rotate0,
+        Orientation::LandscapeFlipped => DisplayRotation::Rotate180,
+    }
+}
+
+/// The display together with its reset line, so a display that stopped
+/// answering can be brought back without rebooting.
+pub struct Oled {
+    // only `None` halfway through `recover` and while `command` runs
+    link: Option<I2cInterfaceTypeAlias>,
+    rotation: DisplayRotation,
+    // sent last and not yet known to have arrived
+    pending: Option<Area>,
+    reset: PA9<Output<PushPull>>,
+    delay: McycleDelay,
+}
+
+impl Oled {
+    /// Takes over the bus, call `init` before drawing.
+    pub fn new(
+        i2c: I2cBus,
+        dma: pac::DMA0,
+        buffers: &'static mut DoubleBuffer<TRANSFER_LEN>,
+        reset: PA9<Output<PushPull>>,
+        delay: McycleDelay,
+    ) -> Self {
+        Self {
+            link: Some(DisplayLink::new(i2c, dma, buffers)),
+            rotation: DisplayRotation::Rotate90,
+            pending: None,
+            reset,
+            delay,
+        }
+    }
+
+    /// Resets the panel through its reset line and sets it up for `settings`.
+    pub fn init(
+        &mut self,
+        settings: &Settings,
+        frame: &mut Framebuffer,
+    ) -> Result<(), DisplayError> {
+        self.reset.set_low().unwrap();
+        self.delay.delay_us(10);
+        self.reset.set_high().unwrap();
+        // OLED datasheet recommends 3 us delay to wait for init.
+        self.delay.delay_us(3);
+
+        self.command(|disp| disp.init_with_addr_mode(AddrMode::Horizontal))
+            .map_err(|_| DisplayError::BusWriteError)?;
+        self.apply_settings(settings, frame)
+    }
+
+    pub fn apply_settings(
+        &mut self,
+        settings: &Settings,
+        frame: &mut Framebuffer,
+    ) -> Result<(), DisplayError> {
+        let layout = Layout::new(settings);
+        // what is on its way was drawn for the old orientation
+        self.settle(frame)?;
+        self.rotation = rotation(&layout);
+        let rotation = self.rotation;
+        self.command(|disp| disp.set_rotation(rotation))?;
+        frame.set_transposed(!layout.is_landscape());
+        self.command(|disp| disp.set_brightness(Brightness::custom(0xF1, settings.contrast())))
+    }
+
+    /// Dims or turns off the panel. Its memory is kept while off, so it
+    /// comes back with the last frame.
+    pub fn set_power(&mut self, power: Power, settings: &Settings) -> Result<(), DisplayError> {
+        self.command(|disp| match power {
+            Power::On => {
+                disp.set_brightness(Brightness::custom(0xF1, settings.contrast()))?;
+                disp.set_display_on(true)
+            }
+            Power::Dim => {
+                disp.set_brightness(Brightness::DIMMEST)?;
+                disp.set_display_on(true)
+            }
+            Power::Off => disp.set_display_on(false),
+        })
+    }
+
+    // Sends only the area that changed since the last flush. The data goes
+    // out by DMA, so this returns before the display has it. The next area
+    // is queued while the last one may still be going out, it counts as
+    // shown once the wait for it went through.
+    pub fn flush(&mut self, frame: &mut Framebuffer) -> Result<(), DisplayError> {
+        // usually the last transfer is long done, then it isn't sent again
+        if self.link().is_idle() {
+            self.settle(frame)?;
+        }
+        // what is still on its way may have changed back since, and only
+        // differs from what the panel will show
+        let area = match (frame.dirty_area(), self.pending) {
+            (Some(dirty), Some(pending)) => dirty.union(&pending),
+            (Some(area), None) | (None, Some(area)) => area,
+            (None, None) => return Ok(()),
+        };
+        let column = area.column + DisplaySize96x16::OFFSETX;
+        let page = area.page + DisplaySize96x16::OFFSETY / 8;
+        let len = self.link().queue_area(frame, &area, column, page);
+
+        self.settle(frame)?;
+        self.link().start_transfer(len)?;
+        self.pending = Some(area);
+        Ok(())
+    }
+
+    // Waits for the transfer in flight and counts its area as shown.
+    fn settle(&mut self, frame: &mut Framebuffer) -> Result<(), DisplayError> {
+        self.link().wait()?;
+        if let Some(area) = self.pending.take() {
+            frame.commit_area(&area, self.link().sent_area());
+        }
+        Ok(())
+    }
+
+    /// Starts over after a failed transfer: resets the I2C peripheral and
+    /// the panel, then sets both up again. The next flush sends a full frame.
+    pub fn recover(
+        &mut self,
+        settings: &Settings,
+        frame: &mut Framebuffer,
+        afio: &mut hal::afio::Afio,
+        rcu: &mut hal::rcu::Rcu,
+    ) -> Result<(), DisplayError> {
+        let (i2c, dma, buffers) = self.link.take().unwrap().release();
+        self.pending = None;
+        let (i2c0, (scl, sda)) = i2c.release();
+
+        // a NACK or a glitch can leave the peripheral thinking the bus is busy
+        unsafe {
+            let rcu = &*pac::RCU::ptr();
+            rcu.apb1rst.modify(|_, w| w.i2c0rst().set_bit());
+            rcu.apb1rst.modify(|_, w| w.i2c0rst().clear_bit());
+        }
+
+        let i2c = setup_i2c(i2c0, scl, sda, afio, rcu);
+        self.link = Some(DisplayLink::new(i2c, dma, buffers));
+
+        self.delay.delay_ms(1);
+        frame.invalidate();
+        self.init(settings, frame)
+    }
+
+    fn link(&mut self) -> &mut I2cInterfaceTypeAlias {
+        self.link.as_mut().unwrap()
+    }
+
+    // Runs `f` on a driver wrapped around the link for the time being, it
+    // keeps no state of its own besides the rotation.
+    fn command<R>(&mut self, f: impl FnOnce(&mut DisplayTypeAlias) -> R) -> R {
+        let link = self.link.take().unwrap();
+        let mut disp = Ssd1306::new(link, DisplaySize96x16, self.rotation);
+        let result = f(&mut disp);
+        self.link = Some(disp.release());
+        result
+    }
+}
@@ -0,0 +1,180 @@
+use core::fmt::{self, Write};
+use core::panic::PanicInfo;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use gd32vf103_pac as pac;
+use heapless::String;
+use pinecil_tetris::input::Button;
+use pinecil_tetris::layout::Layout;
+use pinecil_tetris::render;
+use ssd1306::{prelude::*, I2CDisplayInterface, Ssd1306};
+
+use crate::crash_i2c::CrashI2c;
+use crate::flash::Storage;
+use crate::is_pressed;
+use crate::oled::rotation;
+use crate::watchdog;
+
+pub const MESSAGE_LEN: usize = 120;
+
+// The last 256 bytes of RAM, kept out of RAM in memory.x so neither the
+// runtime nor the stack touch them and the record survives a reset.
+const RECORD_ADDR: usize = 0x2000_7F00;
+const RECORD_MAGIC: u32 = 0x5041_4E43;
+// Left in place once the crash screen was acknowledged, so a debugger can
+// still read the record.
+const RECORD_SEEN: u32 = 0x5345_454E;
+
+// The buttons in GPIOB CTL0: PB0 pulled down through OCTL, PB1 floating
+// on its external pull-down, both inputs
+const CTL0_PB0_PB1: u32 = 0xFF;
+const CTL0_PB0_PULL_PB1_FLOATING: u32 = 0b1000 | (0b0100 << 4);
+const OCTL_PB0: u32 = 1 << 0;
+const APB2EN_PBEN: u32 = 1 << 3;
+
+#[repr(C)]
+struct PanicRecord {
+    magic: u32,
+    len: u32,
+    message: [u8; MESSAGE_LEN],
+}
+
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+// Cuts the message off instead of failing when it doesn't fit.
+struct Truncate<'a>(&'a mut String<MESSAGE_LEN>);
+
+impl Write for Truncate<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.0.push(c).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The message of a panic from before the last reset that nobody saw,
+/// because the reset came from somewhere else than the crash screen.
+pub fn take_last_panic() -> Option<String<MESSAGE_LEN>> {
+    let saved = unsafe { ptr::read_volatile(RECORD_ADDR as *const PanicRecord) };
+    if saved.magic != RECORD_MAGIC {
+        return None;
+    }
+    mark_seen();
+
+    let len = (saved.len as usize).min(MESSAGE_LEN);
+    let text = core::str::from_utf8(&saved.message[..len]).ok()?;
+    let mut message = String::new();
+    let _ = Truncate(&mut message).write_str(text);
+    Some(message)
+}
+
+fn store(message: &str) {
+    let mut saved = PanicRecord {
+        magic: RECORD_MAGIC,
+        len: message.len() as u32,
+        message: [0; MESSAGE_LEN],
+    };
+    saved.message[..message.len()].copy_from_slice(message.as_bytes());
+    unsafe { ptr::write_volatile(RECORD_ADDR as *mut PanicRecord, saved) };
+}
+
+fn mark_seen() {
+    let record = RECORD_ADDR as *mut PanicRecord;
+    unsafe { ptr::write_volatile(ptr::addr_of_mut!((*record).magic), RECORD_SEEN) };
+}
+
+// Sets the display up from scratch, main's copy may be mid-transfer. It
+// only takes over the registers, main never gets to run again.
+fn show(message: &str) {
+    let p = unsafe { pac::Peripherals::steal() };
+
+    // stop a DMA frame transfer
+    unsafe { (*pac::DMA0::ptr()).ch5ctl.write(|w| w.bits(0)) };
+
+    let settings = Storage::new(p.FMC).load_settings();
+    let rotation = rotation(&Layout::new(&settings));
+
+    let i2c = CrashI2c::new(p.I2C0, &p.RCU, &p.GPIOB);
+    let interface = I2CDisplayInterface::new(i2c);
+    let mut disp =
+        Ssd1306::new(interface, DisplaySize96x16, rotation).into_buffered_graphics_mode();
+    if disp.init().is_err() {
+        return;
+    }
+    disp.clear();
+    let _ = render::draw_crash(message, &mut disp);
+    let _ = disp.flush();
+}
+
+// Where first, the screen only fits a few dozen characters and a message
+// can run long. The path is cut to the file name for the same reason.
+fn describe(info: &PanicInfo, out: &mut Truncate<'_>) {
+    if let Some(location) = info.location() {
+        let file = location.file().rsplit('/').next().unwrap_or("");
+        let _ = write!(out, "{}:{} ", file, location.line());
+    }
+    let _ = match info.message() {
+        Some(message) => out.write_fmt(*message),
+        None => out.write_str("panic"),
+    };
+}
+
+// Sets the button pins up the way main does. The panic may have come
+// before main got to them, and a floating - would read as pressed.
+fn setup_buttons() {
+    unsafe {
+        (*pac::RCU::ptr())
+            .apb2en
+            .modify(|r, w| w.bits(r.bits() | APB2EN_PBEN));
+        let gpiob = &*pac::GPIOB::ptr();
+        gpiob.octl.modify(|r, w| w.bits(r.bits() & !OCTL_PB0));
+        gpiob
+            .ctl0
+            .modify(|r, w| w.bits((r.bits() & !CTL0_PB0_PB1) | CTL0_PB0_PULL_PB1_FLOATING));
+    }
+}
+
+fn wait_for_button() {
+    setup_buttons();
+    let any_pressed = || {
+        watchdog::keep_alive();
+        is_pressed(Button::Minus) || is_pressed(Button::Plus)
+    };
+
+    // whatever was held when it crashed doesn't count
+    while any_pressed() {}
+    while !any_pressed() {}
+}
+
+// The Bumblebee core has no reset register of its own, this goes through
+// the debug unit like the vendor SDK does.
+fn reset() -> ! {
+    const DBGMCU_KEY: *mut u32 = 0xE004_200C as *mut u32;
+    const DBGMCU_CMD: *mut u32 = 0xE004_2008 as *mut u32;
+    unsafe {
+        ptr::write_volatile(DBGMCU_KEY, 0x4B5A_6978);
+        ptr::write_volatile(DBGMCU_CMD, 1);
+    }
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    unsafe { riscv::interrupt::disable() };
+
+    // a panic while showing the crash screen goes straight to waiting
+    if !PANICKING.swap(true, Ordering::Relaxed) {
+        let mut message = String::new();
+        describe(info, &mut Truncate(&mut message));
+        store(&message);
+        show(&message);
+    }
+
+    wait_for_button();
+    mark_seen();
+    reset();
+}
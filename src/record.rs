@@ -0,0 +1,64 @@
+//! The framing of the records kept in flash: two magic bytes telling the
+//! kinds apart and a version up front, a checksum in the last byte and the
+//! payload in between.
+
+// Magic and version.
+const HEADER_LEN: usize = 3;
+
+/// One kind of record.
+pub struct Framing {
+    magic: [u8; 2],
+    /// Bumped whenever the payload layout changes, older records are
+    /// discarded.
+    version: u8,
+    len: usize,
+}
+
+impl Framing {
+    pub const fn new(magic: [u8; 2], version: u8, payload_len: usize) -> Self {
+        let len = HEADER_LEN + payload_len + 1;
+        Self {
+            magic,
+            version,
+            // kept even since flash is programmed in halfwords, the byte
+            // it takes is zero
+            len: len + len % 2,
+        }
+    }
+
+    /// Bytes the whole record takes.
+    pub const fn size(&self) -> usize {
+        self.len
+    }
+
+    /// Where the payload goes in a record `size` bytes long, the padding
+    /// included.
+    pub fn payload_mut<'a>(&self, record: &'a mut [u8]) -> &'a mut [u8] {
+        &mut record[HEADER_LEN..self.len - 1]
+    }
+
+    /// Puts the magic, version and checksum around the payload already
+    /// written to `record`.
+    pub fn seal(&self, record: &mut [u8]) {
+        record[..2].copy_from_slice(&self.magic);
+        record[2] = self.version;
+        record[self.len - 1] = checksum(&record[..self.len - 1]);
+    }
+
+    /// The payload of `record`, `None` if it is blank, corrupt or from
+    /// another version.
+    pub fn open<'a>(&self, record: &'a [u8]) -> Option<&'a [u8]> {
+        if record.len() < self.len
+            || record[..2] != self.magic
+            || record[2] != self.version
+            || record[self.len - 1] != checksum(&record[..self.len - 1])
+        {
+            return None;
+        }
+        Some(&record[HEADER_LEN..self.len - 1])
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0xA5, |acc, b| acc.rotate_left(1) ^ b)
+}
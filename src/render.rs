@@ -0,0 +1,369 @@
+use embedded_graphics::mono_font::ascii::FONT_4X6;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::text::{Alignment, Baseline, Text};
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle, Triangle},
+};
+use numtoa::NumToA;
+
+use crate::app::{App, GameMode, Screen};
+use crate::controls::Command;
+use crate::digits::{self, BUF_LEN};
+use crate::game::{MoveDirection, TICK_HZ};
+use crate::layout::Layout;
+use crate::settings::SettingsItem;
+
+/// Characters of the small font across the 16 pixels of the screen, the
+/// most a label or a name drawn on a line of its own can have.
+pub const LABEL_CHARS: usize = 16 / FONT_4X6.character_size.width as usize;
+
+/// Draws the current screen of `app`, the caller clears and flushes the display.
+pub fn draw<D>(app: &App, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let layout = Layout::new(app.settings()).shifted(app.hud_shift());
+
+    match app.screen() {
+        Screen::Title => draw_title(&layout, disp),
+        Screen::Settings => draw_settings(app, &layout, disp),
+        Screen::ModeSelect => draw_mode_select(app.mode(), &layout, disp),
+        Screen::Playing | Screen::Demo => draw_game(app, &layout, disp),
+        Screen::Paused => draw_paused(app, &layout, disp),
+        Screen::GameOver => draw_game_over(app, &layout, disp),
+        Screen::Diagnostics => draw_diagnostics(app, &layout, disp),
+        Screen::Mapping => draw_mapping(app, &layout, disp),
+    }
+}
+
+fn draw_text<D>(text: &str, (x, y): (i32, i32), disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    debug_assert!(text.len() <= LABEL_CHARS, "{:?} is too long", text);
+    let character_style = MonoTextStyle::new(&FONT_4X6, BinaryColor::On);
+
+    Text::with_alignment(text, Point::new(x, y), character_style, Alignment::Center).draw(disp)?;
+    Ok(())
+}
+
+// Numbers are shortened and drawn in the small font, so they fit a line.
+fn draw_number<D>(value: u32, at: (i32, i32), disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut buf = [0u8; BUF_LEN];
+    digits::draw(digits::compact(value, &mut buf), at, disp)
+}
+
+/// Fills the display with `text`, wrapped at the display width, for the
+/// crash screen. Whatever doesn't fit is cut off.
+pub fn draw_crash<D>(text: &str, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let character_style = MonoTextStyle::new(&FONT_4X6, BinaryColor::On);
+    let size = disp.bounding_box().size;
+    let columns = (size.width / 4).max(1) as usize;
+    let rows = (size.height / 6) as usize;
+
+    let bytes = text.as_bytes();
+    for (row, chunk) in bytes.chunks(columns).take(rows).enumerate() {
+        // the font is ASCII only, anything else shows as '?'
+        let mut line = [b'?'; 32];
+        let len = chunk.len().min(line.len());
+        for (slot, &byte) in line.iter_mut().zip(chunk) {
+            if byte.is_ascii() {
+                *slot = byte;
+            }
+        }
+        let line = core::str::from_utf8(&line[..len]).unwrap_or("");
+
+        Text::with_baseline(
+            line,
+            Point::new(0, row as i32 * 6),
+            character_style,
+            Baseline::Top,
+        )
+        .draw(disp)?;
+    }
+    Ok(())
+}
+
+fn draw_title<D>(layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    draw_text("TET", layout.line(0), disp)?;
+    draw_text("RIS", layout.line(1), disp)?;
+
+    draw_text("+Go", layout.line(6), disp)?;
+    draw_text("-Set", layout.line(7), disp)
+}
+
+fn draw_settings<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let settings = app.settings();
+    let item = app.settings_item();
+    let mut buf = [0u8; 20];
+
+    draw_text("Set", layout.line(0), disp)?;
+
+    draw_text("+", layout.line(2), disp)?;
+    draw_text(item.label(), layout.line(3), disp)?;
+
+    let value = match item {
+        SettingsItem::Brightness => (settings.brightness + 1).numtoa_str(10, &mut buf),
+        SettingsItem::StartLevel => settings.start_level.numtoa_str(10, &mut buf),
+        SettingsItem::Controls => match settings.mapping.preset() {
+            Some(preset) => preset.name(),
+            None => "Own",
+        },
+        SettingsItem::Handedness => {
+            if settings.left_handed {
+                "L"
+            } else {
+                "R"
+            }
+        }
+        SettingsItem::View => {
+            if settings.landscape {
+                "Land"
+            } else {
+                "Port"
+            }
+        }
+        SettingsItem::Clears => {
+            if settings.forgiving_clears {
+                "-1"
+            } else {
+                "All"
+            }
+        }
+        SettingsItem::Columns => layout.board().0.numtoa_str(10, &mut buf),
+        SettingsItem::Rows => layout.board().1.numtoa_str(10, &mut buf),
+        SettingsItem::CellWidth => settings.cell_width.numtoa_str(10, &mut buf),
+        SettingsItem::CellHeight => settings.cell_height.numtoa_str(10, &mut buf),
+        SettingsItem::SpawnDelay => settings.spawn_delay.numtoa_str(10, &mut buf),
+        SettingsItem::Mapping | SettingsItem::Diagnostics | SettingsItem::Exit => "",
+    };
+
+    draw_text("-", layout.line(4), disp)?;
+    draw_text(value, layout.line(5), disp)
+}
+
+fn draw_mapping<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let slot = app.mapping_slot();
+
+    draw_text("Map", layout.line(0), disp)?;
+
+    draw_text("+", layout.line(2), disp)?;
+    draw_text(slot.label(), layout.line(3), disp)?;
+
+    draw_text("-", layout.line(4), disp)?;
+    draw_text(
+        app.settings().mapping.get(slot).name(),
+        layout.line(5),
+        disp,
+    )
+}
+
+fn draw_diagnostics<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let diagnostics = app.diagnostics();
+
+    draw_text("Diag", layout.line(0), disp)?;
+
+    draw_text("I2C", layout.line(2), disp)?;
+    draw_number(diagnostics.display_errors, layout.line(3), disp)?;
+
+    draw_text("Rcv", layout.line(4), disp)?;
+    draw_number(diagnostics.display_resets, layout.line(5), disp)?;
+
+    draw_text("Boot", layout.line(6), disp)?;
+    draw_text(diagnostics.reset_cause.name(), layout.line(7), disp)
+}
+
+fn draw_mode_select<D>(mode: GameMode, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    draw_text("Mode", layout.line(0), disp)?;
+
+    draw_text("+", layout.line(2), disp)?;
+    draw_text(mode.name(), layout.line(3), disp)?;
+
+    draw_text("-", layout.line(4), disp)?;
+    draw_text("Go", layout.line(5), disp)
+}
+
+// The board is hidden so the pause can't be used to plan ahead.
+fn draw_paused<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    draw_text("Pau-", layout.line(0), disp)?;
+    draw_text("sed", layout.line(1), disp)?;
+
+    draw_text("Pts", layout.line(2), disp)?;
+    draw_number(app.game().get_score(), layout.line(3), disp)?;
+
+    if matches!(app.supply(), Some(supply) if supply.low) {
+        draw_text("LoV", layout.line(4), disp)?;
+    }
+
+    draw_text("+&-", layout.line(6), disp)?;
+    draw_text("go", layout.line(7), disp)
+}
+
+fn draw_game_over<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let stats = app.stats();
+
+    if stats.cleared {
+        draw_text("Done", layout.line(0), disp)?;
+    } else {
+        draw_text("Game", layout.line(0), disp)?;
+        draw_text("over", layout.line(1), disp)?;
+    }
+
+    draw_text("Pts", layout.line(2), disp)?;
+    draw_number(stats.score, layout.line(3), disp)?;
+
+    draw_text("Pcs", layout.line(4), disp)?;
+    draw_number(stats.pieces, layout.line(5), disp)?;
+
+    if stats.mode == GameMode::Sprint {
+        let mut buf = [0u8; BUF_LEN];
+        draw_text("Time", layout.line(6), disp)?;
+        digits::draw(
+            digits::clock(stats.ticks / TICK_HZ, &mut buf),
+            layout.line(7),
+            disp,
+        )?;
+    }
+    Ok(())
+}
+
+fn draw_supply<D>(bars: usize, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let fill = PrimitiveStyle::with_fill(BinaryColor::On);
+    let (x, y) = layout.supply();
+
+    for i in 0..bars as i32 {
+        let corner = if layout.is_landscape() {
+            Point::new(x, y + 3 * i)
+        } else {
+            Point::new(x + 3 * i, y)
+        };
+        Rectangle::new(corner, Size::new(2, 2))
+            .into_styled(fill)
+            .draw(disp)?;
+    }
+    Ok(())
+}
+
+// A small triangle pointing the way the next bounce move goes.
+fn draw_arrow<D>(direction: MoveDirection, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (x, y) = layout.arrow();
+    let (dx, dy) = match direction {
+        MoveDirection::Right => layout.right(),
+        MoveDirection::Left => {
+            let (dx, dy) = layout.right();
+            (-dx, -dy)
+        }
+    };
+
+    Triangle::new(
+        Point::new(x + 2 * dx, y + 2 * dy),
+        Point::new(x - 2 * dx - 2 * dy, y - 2 * dy - 2 * dx),
+        Point::new(x - 2 * dx + 2 * dy, y - 2 * dy + 2 * dx),
+    )
+    .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+    .draw(disp)
+}
+
+fn draw_game<D>(app: &App, layout: &Layout, disp: &mut D) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let game = app.game();
+
+    let thin_stroke = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+
+    let grid = app.animations().grid(game);
+
+    let mut buf = [0u8; BUF_LEN];
+
+    let (cell_width, cell_height) = layout.cell_size();
+    let cell_size = Size::new(cell_width, cell_height);
+
+    let ((x, y), (width, height)) = layout.hud();
+    Rectangle::new(Point::new(x, y), Size::new(width, height))
+        .into_styled(thin_stroke)
+        .draw(disp)?;
+
+    match app.supply() {
+        Some(supply) if supply.low => draw_text("LoV", layout.score(), disp)?,
+        _ => {
+            let score = digits::compact(game.get_score(), &mut buf);
+            if digits::width(score) <= layout.score_room() {
+                digits::draw(score, layout.score(), disp)?;
+            } else {
+                let (top, bottom) = digits::split(score);
+                let (top_at, bottom_at) = layout.score_stacked();
+                digits::draw(top, top_at, disp)?;
+                digits::draw(bottom, bottom_at, disp)?;
+            }
+        }
+    }
+
+    if let Some(supply) = app.supply() {
+        draw_supply(supply.bars(), layout, disp)?;
+    }
+
+    // only the bounce mover has a direction to guess, the demo doesn't use it
+    if app.screen() == Screen::Playing
+        && app.settings().mapping.contains(Command::Move)
+        && game.get_block().is_some()
+    {
+        draw_arrow(game.next_move_direction(), layout, disp)?;
+    }
+
+    if let Some(block) = game.get_block() {
+        for (x, y) in block.cells() {
+            let (x, y) = layout.cell(x, y);
+            Rectangle::new(Point::new(x, y), cell_size)
+                .into_styled(thin_stroke)
+                .draw(disp)?;
+        }
+    }
+
+    for (i, row) in grid.iter().enumerate().take(game.height()) {
+        for j in 0..game.width() {
+            let (x, y) = layout.cell(j as i32, i as i32);
+            if row & (1 << j) != 0 {
+                Rectangle::new(Point::new(x, y), cell_size)
+                    .into_styled(thin_stroke)
+                    .draw(disp)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use crate::controls::{Mapping, Preset, MAPPING_LEN};
+use crate::game::{Rules, MAX_HEIGHT, MAX_WIDTH, MIN_HEIGHT, MIN_WIDTH, TICK_HZ};
+use crate::layout::{Layout, MAX_CELL};
+use crate::record::Framing;
+
+pub const SETTINGS_VERSION: u8 = 4;
+
+// Brightness, start level, flags, spawn delay, the mapping and the board.
+const PAYLOAD_LEN: usize = 8 + MAPPING_LEN;
+
+const FRAMING: Framing = Framing::new(*b"PT", SETTINGS_VERSION, PAYLOAD_LEN);
+
+/// Size of the flash record.
+pub const RECORD_LEN: usize = FRAMING.size();
+
+pub const BRIGHTNESS_LEVELS: u8 = 5;
+pub const MAX_START_LEVEL: u8 = 9;
+/// In quarter seconds.
+pub const MAX_SPAWN_DELAY: u8 = 5;
+// Rows the menu adds at a time, the last step stops at what fits.
+const ROWS_STEP: u8 = 4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Settings {
+    pub brightness: u8,
+    pub start_level: u8,
+    /// What the buttons do in a game.
+    pub mapping: Mapping,
+    /// Turn the screen around and swap the buttons for holding the iron in the left hand.
+    pub left_handed: bool,
+    /// Use the panel's native wide orientation.
+    pub landscape: bool,
+    /// Let rows with a single gap clear, the screen borders are hard to make out.
+    pub forgiving_clears: bool,
+    /// Delay before the next piece spawns, in quarter seconds.
+    pub spawn_delay: u8,
+    /// Columns and rows of the well, `Layout` cuts them down to what
+    /// fits the screen with the cells below.
+    pub columns: u8,
+    pub rows: u8,
+    /// Pixels of a cell across a column and along a row, 1 to `MAX_CELL`.
+    pub cell_width: u8,
+    pub cell_height: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            brightness: BRIGHTNESS_LEVELS - 1,
+            start_level: 0,
+            mapping: Mapping::default(),
+            left_handed: false,
+            landscape: false,
+            forgiving_clears: true,
+            spawn_delay: 5,
+            columns: 8,
+            rows: 32,
+            cell_width: 2,
+            cell_height: 2,
+        }
+    }
+}
+
+impl Settings {
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        // bit 0 used to swap the buttons, the mapping does that now
+        let flags = (self.forgiving_clears as u8) << 1
+            | (self.left_handed as u8) << 2
+            | (self.landscape as u8) << 3;
+        let mut record = [0u8; RECORD_LEN];
+        let payload = FRAMING.payload_mut(&mut record);
+        payload[..4].copy_from_slice(&[self.brightness, self.start_level, flags, self.spawn_delay]);
+        payload[4..4 + MAPPING_LEN].copy_from_slice(&self.mapping.to_bytes());
+        payload[8..12].copy_from_slice(&[
+            self.columns,
+            self.rows,
+            self.cell_width,
+            self.cell_height,
+        ]);
+        FRAMING.seal(&mut record);
+        record
+    }
+
+    /// Parses a stored record, `None` if it is blank, corrupt or from another version.
+    pub fn from_bytes(record: &[u8]) -> Option<Self> {
+        let payload = FRAMING.open(record)?;
+        let settings = Self {
+            brightness: payload[0],
+            start_level: payload[1],
+            mapping: Mapping::from_bytes(&payload[4..4 + MAPPING_LEN])?,
+            forgiving_clears: payload[2] & 2 != 0,
+            left_handed: payload[2] & 4 != 0,
+            landscape: payload[2] & 8 != 0,
+            spawn_delay: payload[3],
+            columns: payload[8],
+            rows: payload[9],
+            cell_width: payload[10],
+            cell_height: payload[11],
+        };
+
+        if settings.brightness >= BRIGHTNESS_LEVELS
+            || settings.start_level > MAX_START_LEVEL
+            || settings.spawn_delay > MAX_SPAWN_DELAY
+            || !(MIN_WIDTH..=MAX_WIDTH).contains(&(settings.columns as usize))
+            || !(MIN_HEIGHT..=MAX_HEIGHT).contains(&(settings.rows as usize))
+            || !(1..=MAX_CELL.0).contains(&settings.cell_width)
+            || !(1..=MAX_CELL.1).contains(&settings.cell_height)
+        {
+            return None;
+        }
+        Some(settings)
+    }
+
+    /// Whether the + and - buttons trade roles, for the left-handed grip.
+    /// The mapping applies after the swap.
+    pub const fn buttons_swapped(&self) -> bool {
+        self.left_handed
+    }
+
+    /// SSD1306 contrast value for the brightness level.
+    pub const fn contrast(&self) -> u8 {
+        match self.brightness {
+            0 => 0x00,
+            1 => 0x2F,
+            2 => 0x5F,
+            3 => 0x9F,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        let (width, height) = Layout::new(self).board();
+        Rules {
+            start_level: self.start_level,
+            clear_threshold: width as usize - self.forgiving_clears as usize,
+            spawn_delay: self.spawn_delay * (TICK_HZ / 4) as u8,
+            width,
+            height,
+            ..Rules::default()
+        }
+    }
+
+    /// Steps the value of `item` to its next option, wrapping around.
+    pub fn cycle(&mut self, item: SettingsItem) {
+        match item {
+            SettingsItem::Brightness => {
+                self.brightness = (self.brightness + 1) % BRIGHTNESS_LEVELS;
+            }
+            SettingsItem::StartLevel => {
+                self.start_level = (self.start_level + 1) % (MAX_START_LEVEL + 1);
+            }
+            // an edited mapping starts over from the first preset
+            SettingsItem::Controls => {
+                let preset = match self.mapping.preset() {
+                    Some(preset) => preset.next(),
+                    None => Preset::Classic,
+                };
+                self.mapping = preset.mapping();
+            }
+            SettingsItem::Handedness => self.left_handed = !self.left_handed,
+            SettingsItem::View => self.landscape = !self.landscape,
+            SettingsItem::Clears => self.forgiving_clears = !self.forgiving_clears,
+            // steps from what the screen shows, so every press changes it
+            SettingsItem::Columns => {
+                let layout = Layout::new(self);
+                let (columns, _) = layout.board();
+                self.columns = if columns < layout.room().0 {
+                    columns + 1
+                } else {
+                    MIN_WIDTH as u8
+                };
+            }
+            SettingsItem::Rows => {
+                let layout = Layout::new(self);
+                let (_, rows) = layout.board();
+                let room = layout.room().1;
+                self.rows = if rows >= room {
+                    MIN_HEIGHT as u8
+                } else {
+                    (rows + ROWS_STEP).min(room)
+                };
+            }
+            SettingsItem::CellWidth => self.cell_width = self.cell_width % MAX_CELL.0 + 1,
+            SettingsItem::CellHeight => self.cell_height = self.cell_height % MAX_CELL.1 + 1,
+            SettingsItem::SpawnDelay => {
+                self.spawn_delay = (self.spawn_delay + 1) % (MAX_SPAWN_DELAY + 1);
+            }
+            SettingsItem::Mapping | SettingsItem::Diagnostics | SettingsItem::Exit => {}
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingsItem {
+    Brightness,
+    StartLevel,
+    /// Picks a preset mapping.
+    Controls,
+    /// Opens the mapping editor instead of holding a value.
+    Mapping,
+    Handedness,
+    View,
+    Clears,
+    Columns,
+    Rows,
+    CellWidth,
+    CellHeight,
+    SpawnDelay,
+    /// Opens the diagnostics screen instead of holding a value.
+    Diagnostics,
+    Exit,
+}
+
+impl SettingsItem {
+    pub const fn next(self) -> Self {
+        match self {
+            SettingsItem::Brightness => SettingsItem::StartLevel,
+            SettingsItem::StartLevel => SettingsItem::Controls,
+            SettingsItem::Controls => SettingsItem::Mapping,
+            SettingsItem::Mapping => SettingsItem::Handedness,
+            SettingsItem::Handedness => SettingsItem::View,
+            SettingsItem::View => SettingsItem::Clears,
+            SettingsItem::Clears => SettingsItem::Columns,
+            SettingsItem::Columns => SettingsItem::Rows,
+            SettingsItem::Rows => SettingsItem::CellWidth,
+            SettingsItem::CellWidth => SettingsItem::CellHeight,
+            SettingsItem::CellHeight => SettingsItem::SpawnDelay,
+            SettingsItem::SpawnDelay => SettingsItem::Diagnostics,
+            SettingsItem::Diagnostics => SettingsItem::Exit,
+            SettingsItem::Exit => SettingsItem::Brightness,
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            SettingsItem::Brightness => "Brt",
+            SettingsItem::StartLevel => "Lvl",
+            SettingsItem::Controls => "Keys",
+            SettingsItem::Mapping => "Map",
+            SettingsItem::Handedness => "Hand",
+            SettingsItem::View => "View",
+            SettingsItem::Clears => "Clr",
+            SettingsItem::Columns => "Cols",
+            SettingsItem::Rows => "Rows",
+            SettingsItem::CellWidth => "CelW",
+            SettingsItem::CellHeight => "CelH",
+            SettingsItem::SpawnDelay => "Wait",
+            SettingsItem::Diagnostics => "Diag",
+            SettingsItem::Exit => "Save",
+        }
+    }
+}
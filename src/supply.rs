@@ -0,0 +1,91 @@
+/// Full scale of the 12 bit ADC.
+pub const ADC_MAX: u32 = 4095;
+/// ADC reference, the MCU supply.
+pub const VREF_MV: u32 = 3300;
+/// The input voltage reaches the ADC through a 1:11 divider.
+pub const DIVIDER: u32 = 11;
+
+/// Below this the 3.3 V regulator is about to drop out, so the game is
+/// saved while there's still time.
+pub const LOW_MV: u32 = 4_600;
+// The warning only clears this far above `LOW_MV`, so noise doesn't
+// make it flicker.
+const HYSTERESIS_MV: u32 = 300;
+
+// Input voltages for one, two and three bars of the indicator:
+// USB 5 V, 9 V and 12 V and up.
+const BAR_MV: [u32; 3] = [4_600, 8_500, 11_500];
+
+/// Something that reads the raw input voltage divider output.
+pub trait VoltageSensor {
+    /// A 12 bit sample, 0 to `ADC_MAX`.
+    fn read_raw(&mut self) -> u16;
+}
+
+pub const fn to_millivolts(raw: u16) -> u32 {
+    raw as u32 * VREF_MV * DIVIDER / ADC_MAX
+}
+
+/// The input voltage as the HUD shows it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Supply {
+    pub millivolts: u32,
+    /// Brown-out is close.
+    pub low: bool,
+}
+
+impl Supply {
+    /// Bars of the power indicator, 0 to 3.
+    pub fn bars(&self) -> usize {
+        BAR_MV.iter().filter(|&&mv| self.millivolts >= mv).count()
+    }
+}
+
+/// Samples the input voltage and decides when it's getting too low.
+pub struct SupplyMonitor<S> {
+    sensor: S,
+    // filtered reading, `None` until the first sample
+    millivolts: Option<u32>,
+    low: bool,
+}
+
+impl<S: VoltageSensor> SupplyMonitor<S> {
+    pub const fn new(sensor: S) -> Self {
+        Self {
+            sensor,
+            millivolts: None,
+            low: false,
+        }
+    }
+
+    /// Takes a sample, returns `true` when this one made the voltage low.
+    pub fn update(&mut self) -> bool {
+        let sample = to_millivolts(self.sensor.read_raw());
+        // a light low-pass against ripple, seeded with the first sample
+        let millivolts = match self.millivolts {
+            Some(mv) => (mv * 3 + sample) / 4,
+            None => sample,
+        };
+        self.millivolts = Some(millivolts);
+
+        let was_low = self.low;
+        if millivolts < LOW_MV {
+            self.low = true;
+        } else if millivolts >= LOW_MV + HYSTERESIS_MV {
+            self.low = false;
+        }
+        self.low && !was_low
+    }
+
+    /// The last reading, `None` before the first `update`.
+    pub fn supply(&self) -> Option<Supply> {
+        self.millivolts.map(|millivolts| Supply {
+            millivolts,
+            low: self.low,
+        })
+    }
+
+    pub fn sensor_mut(&mut self) -> &mut S {
+        &mut self.sensor
+    }
+}
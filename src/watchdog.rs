@@ -0,0 +1,76 @@
+use gd32vf103_pac as pac;
+use pinecil_tetris::app::ResetCause;
+
+// FWDGT keys and register bits
+const KEY_RELOAD: u32 = 0xAAAA;
+const KEY_START: u32 = 0xCCCC;
+const KEY_UNLOCK: u32 = 0x5555;
+const STAT_PUD: u32 = 1 << 0;
+const STAT_RUD: u32 = 1 << 1;
+
+// The watchdog runs off the 40 kHz IRC40K, /64 gives 625 counts a second.
+const PSC_DIV64: u32 = 0b100;
+const COUNTS_PER_SEC: u32 = 625;
+/// Longest the main loop may go without feeding it. It wakes up at least
+/// once a second, a flash erase or a display recovery take well under that.
+const TIMEOUT_SECS: u32 = 3;
+
+// RCU RSTSCK reset flags
+const RSTSCK_RSTFC: u32 = 1 << 24;
+const RSTSCK_EPRSTF: u32 = 1 << 26;
+const RSTSCK_PORRSTF: u32 = 1 << 27;
+const RSTSCK_SWRSTF: u32 = 1 << 28;
+const RSTSCK_FWDGTRSTF: u32 = 1 << 29;
+const RSTSCK_LPRSTF: u32 = 1 << 31;
+
+/// The free watchdog. It resets the MCU unless `feed` is called within
+/// `TIMEOUT_SECS`, and once started nothing can stop it.
+pub struct Watchdog {
+    fwdgt: pac::FWDGT,
+}
+
+impl Watchdog {
+    pub fn start(fwdgt: pac::FWDGT) -> Self {
+        unsafe {
+            fwdgt.ctl.write(|w| w.bits(KEY_UNLOCK));
+            while fwdgt.stat.read().bits() & (STAT_PUD | STAT_RUD) != 0 {}
+            fwdgt.psc.write(|w| w.bits(PSC_DIV64));
+            fwdgt.rld.write(|w| w.bits(TIMEOUT_SECS * COUNTS_PER_SEC));
+            fwdgt.ctl.write(|w| w.bits(KEY_RELOAD));
+            fwdgt.ctl.write(|w| w.bits(KEY_START));
+        }
+        Self { fwdgt }
+    }
+
+    pub fn feed(&mut self) {
+        self.fwdgt.ctl.write(|w| unsafe { w.bits(KEY_RELOAD) });
+    }
+}
+
+/// Feeds the watchdog without owning it, for the panic handler.
+pub fn keep_alive() {
+    unsafe { (*pac::FWDGT::ptr()).ctl.write(|w| w.bits(KEY_RELOAD)) };
+}
+
+/// Why the MCU last reset. Clears the flags, so call it once at boot.
+pub fn take_reset_cause() -> ResetCause {
+    let rcu = unsafe { &*pac::RCU::ptr() };
+    let flags = rcu.rstsck.read().bits();
+    rcu.rstsck
+        .modify(|r, w| unsafe { w.bits(r.bits() | RSTSCK_RSTFC) });
+
+    // a power-on reset sets the pin flag too, so it goes first
+    if flags & RSTSCK_FWDGTRSTF != 0 {
+        ResetCause::Watchdog
+    } else if flags & RSTSCK_PORRSTF != 0 {
+        ResetCause::PowerOn
+    } else if flags & RSTSCK_SWRSTF != 0 {
+        ResetCause::Software
+    } else if flags & RSTSCK_LPRSTF != 0 {
+        ResetCause::LowPower
+    } else if flags & RSTSCK_EPRSTF != 0 {
+        ResetCause::Pin
+    } else {
+        ResetCause::PowerOn
+    }
+}